@@ -0,0 +1,74 @@
+use custom_dict::ze_corp::fields::*;
+use custom_dict::ze_corp::messages::*;
+use openfix_messages::chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use openfix_messages::dec_helpers::scan_message_items;
+use openfix_messages::{AsFixMessage, FixFieldList, FixSessionContext, FromFixMessage};
+
+struct TestSession;
+
+impl FixSessionContext for TestSession {
+    fn sender_comp_id(&self) -> &str {
+        "CLIENT"
+    }
+
+    fn target_comp_id(&self) -> &str {
+        "ZE_CORP"
+    }
+
+    fn next_msg_seq_num(&mut self) -> u64 {
+        1
+    }
+
+    fn sending_time(&self) -> DateTime<Utc> {
+        Utc.from_utc_datetime(
+            &NaiveDate::from_ymd_opt(2021, 4, 10)
+                .unwrap()
+                .and_hms_milli_opt(19, 27, 37, 978)
+                .unwrap(),
+        )
+    }
+}
+
+fn round_trip(message: &Message) -> anyhow::Result<Message> {
+    let mut data = Vec::new();
+    message.encode_message(&mut data)?;
+    let items: FixFieldList = scan_message_items(&data).collect();
+    Ok(Message::decode_items(&items)?)
+}
+
+#[test]
+fn test_custom_message() -> anyhow::Result<()> {
+    let message = MessageSendTopSecretAwesomeOrder::builder()
+        .with_session(&mut TestSession)
+        .with_header(|header| header.ze_desk_code(ZeDeskCode::new("DESK1".into())))
+        .secret_order_id(SecretOrderId::new("SECRET1".into()))
+        .target_account(TargetAccount::Ours)
+        .build()?;
+
+    assert_eq!(message.header.msg_type, MsgType::SendTopSecretAwesomeOrder);
+    assert_eq!(
+        round_trip(&Message::SendTopSecretAwesomeOrder(message))?.msg_type(),
+        "ZZX"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_extended_base_message() -> anyhow::Result<()> {
+    let items: FixFieldList = scan_message_items(
+        b"35=D\x0149=CLIENT\x0156=ZE_CORP\x0134=1\x0152=20210410-19:27:37.978\x01\
+11=ORD1\x0155=AAPL\x0154=1\x0160=20210410-19:27:37.978\x0138=100\x0140=1\x01\
+16249=ALICE\x0116250=M\x01",
+    )
+    .collect();
+    let message = MessageNewOrderSingle::decode_items(&items)?;
+
+    assert_eq!(message.user_data, Some(UserData::new("ALICE".into())));
+    assert_eq!(message.target_account, Some(TargetAccount::Mine));
+    assert!(message.extra_fields.is_empty());
+    assert_eq!(
+        round_trip(&Message::NewOrderSingle(message))?.msg_type(),
+        "D"
+    );
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! Procedural macros generating FIX dictionary modules at compile time.
+//!
+//! Use them through `openfix_messages` `macros` feature.
+
+use openfix_spec_generator::{FixSpec, GeneratorOptions, SpecValidationError};
+use proc_macro::TokenStream;
+use quote::quote;
+use std::env;
+use std::path::PathBuf;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Ident, LitBool, LitStr, Token};
+
+/// Dictionary extended by macro input dictionary
+enum BaseDictionary {
+    /// Dictionary bundled with openfix (ie: `fix44`)
+    Builtin(Ident),
+    /// Dictionary file, relative to crate root
+    Path(LitStr),
+}
+
+struct DictionaryInput {
+    path: LitStr,
+    base: Option<BaseDictionary>,
+    unknown_enum_values: bool,
+}
+
+impl Parse for DictionaryInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut output = Self {
+            path: input.parse()?,
+            base: None,
+            unknown_enum_values: false,
+        };
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "base" if output.base.is_none() => {
+                    output.base = Some(if input.peek(LitStr) {
+                        BaseDictionary::Path(input.parse()?)
+                    } else {
+                        BaseDictionary::Builtin(input.parse()?)
+                    });
+                }
+                "unknown_enum_values" => {
+                    output.unknown_enum_values = input.parse::<LitBool>()?.value;
+                }
+                "base" => return Err(syn::Error::new(key.span(), "duplicate `base` argument")),
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "unknown argument, expected `base` or `unknown_enum_values`",
+                    ))
+                }
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// Builtin dictionaries are symlinked in crate `spec` directory, so that they are packaged with it
+fn builtin_dictionary(name: &Ident) -> syn::Result<&'static str> {
+    match name.to_string().as_str() {
+        "fix40" => Ok(include_str!("../spec/FIX40.xml")),
+        "fix41" => Ok(include_str!("../spec/FIX41.xml")),
+        "fix42" => Ok(include_str!("../spec/FIX42.xml")),
+        "fix43" => Ok(include_str!("../spec/FIX43.xml")),
+        "fix44" => Ok(include_str!("../spec/FIX44.xml")),
+        "fixt11" => Ok(include_str!("../spec/FIXT11.xml")),
+        _ => Err(syn::Error::new(
+            name.span(),
+            "unknown base dictionary, expected one of: fix40, fix41, fix42, fix43, fix44, fixt11",
+        )),
+    }
+}
+
+/// Parse dictionary file, whose path is relative to crate root
+fn parse_dictionary(path: &LitStr, tracked_files: &mut Vec<String>) -> syn::Result<FixSpec> {
+    let full_path =
+        PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap_or_default()).join(path.value());
+
+    let spec = openfix_spec_generator::parse(&full_path).map_err(|err| {
+        syn::Error::new(
+            path.span(),
+            format!("cannot parse {}: {:#}", full_path.display(), err),
+        )
+    })?;
+
+    tracked_files.push(full_path.display().to_string());
+    Ok(spec)
+}
+
+fn validation_error(path: &LitStr, err: SpecValidationError) -> syn::Error {
+    err.issues
+        .iter()
+        .map(|issue| syn::Error::new(path.span(), issue))
+        .reduce(|mut output, err| {
+            output.combine(err);
+            output
+        })
+        .unwrap_or_else(|| syn::Error::new(path.span(), err))
+}
+
+fn expand_dictionary(input: DictionaryInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut tracked_files = Vec::new();
+    let overlay = parse_dictionary(&input.path, &mut tracked_files)?;
+
+    let spec = match &input.base {
+        Some(base) => {
+            let mut spec = match base {
+                BaseDictionary::Builtin(name) => {
+                    openfix_spec_generator::parse_str(builtin_dictionary(name)?)
+                        .map_err(|err| syn::Error::new(name.span(), format!("{:#}", err)))?
+                }
+                BaseDictionary::Path(path) => parse_dictionary(path, &mut tracked_files)?,
+            };
+            spec.merge(overlay)
+                .map_err(|err| syn::Error::new(input.path.span(), format!("{:#}", err)))?;
+            spec
+        }
+        None => overlay,
+    };
+
+    spec.validate()
+        .map_err(|err| validation_error(&input.path, err))?;
+
+    let options = GeneratorOptions {
+        enable_unknown_enum_values: input.unknown_enum_values,
+        runtime_crate: Some("openfix_messages".to_string()),
+    };
+    let code = spec
+        .generate_code(&options)
+        .map_err(|err| syn::Error::new(input.path.span(), format!("{:#}", err)))?;
+
+    let fields = code.fields;
+    let messages = code.messages;
+
+    Ok(quote! {
+        // Rebuild crate when dictionaries change
+        const _: &[&[u8]] = &[#(include_bytes!(#tracked_files)),*];
+
+        pub mod fields {
+            #fields
+        }
+
+        pub mod messages {
+            #messages
+        }
+    })
+}
+
+/// Generate `fields` and `messages` modules from a FIX dictionary.
+///
+/// Dictionary path is relative to crate root. It can extend a base dictionary
+/// (`fix40`, `fix41`, `fix42`, `fix43`, `fix44`, `fixt11` or another file path)
+/// with the same rules as `openfix_spec_generator::Builder::add_overlays`.
+///
+/// Generated code requires `openfix_messages` crate.
+///
+/// ```rust,ignore
+/// pub mod ze_corp {
+///     openfix_messages::dictionary!("ZE_CORP.xml", base = fix44);
+/// }
+/// ```
+#[proc_macro]
+pub fn dictionary(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DictionaryInput);
+
+    expand_dictionary(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
@@ -10,8 +10,8 @@ use openfix_messages::FromFixMessage;
 
 #[bench]
 fn bench_deserialize(bencher: &mut Bencher) {
-    bench_data(bencher, b"8=FIX.4.4\x019=80\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=1618082857.9780622\x011128=4\x0193=8\x0189=arthurlm\x0110=239\x01");
-    bench_data(bencher, b"8=FIX.4.4\x019=80\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=1618082857.9780622\x011128=4\x0193=8\x0189=arthurlm\x0110=239\x01\x01\x01\x01");
+    bench_data(bencher, b"8=FIX.4.4\x019=83\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=20210410-19:27:37.978\x011128=4\x0193=8\x0189=arthurlm\x0110=130\x01");
+    bench_data(bencher, b"8=FIX.4.4\x019=83\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=20210410-19:27:37.978\x011128=4\x0193=8\x0189=arthurlm\x0110=130\x01\x01\x01\x01");
 }
 
 fn bench_data(bencher: &mut Bencher, data: &[u8]) {
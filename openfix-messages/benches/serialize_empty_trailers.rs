@@ -4,6 +4,7 @@ extern crate test;
 
 use test::Bencher;
 
+use openfix_messages::chrono::{NaiveDate, TimeZone, Utc};
 use openfix_messages::enc_helpers::FixEnvelopeBuilder;
 use openfix_messages::test_spec::fields::*;
 use openfix_messages::test_spec::messages::*;
@@ -15,7 +16,14 @@ fn build_header() -> MessageHeader {
         sender_comp_id: SenderCompID::new("BROKER".into()),
         target_comp_id: TargetCompID::new("MARKET".into()),
         msg_seq_num: MsgSeqNum::new(23593),
-        sending_time: SendingTime::new(1618082857.9780622),
+        sending_time: SendingTime::new(
+            Utc.from_utc_datetime(
+                &NaiveDate::from_ymd_opt(2021, 4, 10)
+                    .unwrap()
+                    .and_hms_milli_opt(19, 27, 37, 978)
+                    .unwrap(),
+            ),
+        ),
         appl_ver_id: Some(ApplVerID::Fix42),
     }
 }
@@ -29,6 +37,7 @@ fn build_hb() -> MessageHeartbeat {
         header: build_header(),
         trailer: build_trailer(),
         test_req_id: None,
+        extra_fields: vec![],
     }
 }
 
@@ -11,7 +11,7 @@ fn main() {
         .add_path("../protocol-spec/FIXT11.xml")
         .add_path("../protocol-spec/TEST_SPEC.xml")
         .add_path("../protocol-spec/TEST_SPEC_SIG.xml")
-        .enable_rustfmt(true);
+        .enable_unknown_enum_values(env::var("CARGO_FEATURE_UNKNOWN_ENUM_VALUES").is_ok());
 
     fs::create_dir_all("./out-preview").unwrap();
     builder.build("./out-preview").unwrap();
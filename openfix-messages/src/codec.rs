@@ -0,0 +1,354 @@
+//! tokio-util codecs, framing FIX messages over async streams.
+//!
+//! Decoded items are `Result<_, FixParseError>`: a garbled or invalid frame is returned
+//! to the caller (which may ignore or reject it) without closing the stream. Only I/O
+//! errors, and frames longer than the maximum frame length, end the stream.
+
+use bytes::{Buf, BufMut, BytesMut};
+use std::io;
+use std::marker::PhantomData;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::dec_helpers::{
+    find_frame, find_frame_with_max_body_length, scan_message_items, FixEnvelopeValidator,
+    FixFrameStatus,
+};
+use crate::enc_helpers::FixEnvelopeBuilder;
+use crate::{AsFixMessage, FixFieldList, FixParseError, FromFixMessage};
+
+/// Default maximum length of frames read by codecs
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// Codec splitting raw FIX frames, with checked envelope.
+///
+/// Encoded messages are wrapped in envelope (begin string, length and check sum).
+#[derive(Debug)]
+pub struct FixFrameCodec {
+    builder: FixEnvelopeBuilder,
+    validator: FixEnvelopeValidator,
+    max_frame_length: usize,
+}
+
+impl Default for FixFrameCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FixFrameCodec {
+    pub fn new() -> Self {
+        Self {
+            builder: FixEnvelopeBuilder::default(),
+            validator: FixEnvelopeValidator::default(),
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+        }
+    }
+
+    /// Set maximum length of received frames, envelope included.
+    ///
+    /// Decoding a longer frame returns an `io::ErrorKind::InvalidData` error.
+    pub fn max_frame_length(mut self, value: usize) -> Self {
+        self.max_frame_length = value;
+        self
+    }
+
+    /// Set begin string of sent messages, and check received ones have the same
+    pub fn begin_string(mut self, value: &str) -> Self {
+        self.builder = self.builder.begin_string(value);
+        self.validator = self.validator.begin_string(value);
+        self
+    }
+}
+
+impl Decoder for FixFrameCodec {
+    type Item = Result<BytesMut, FixParseError>;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(split_frame(src, self.max_frame_length)?.map(|frame| self.check_frame(frame?)))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(split_last_frame(src, self.max_frame_length)?.map(|frame| self.check_frame(frame?)))
+    }
+}
+
+impl FixFrameCodec {
+    fn check_frame(&self, frame: BytesMut) -> Result<BytesMut, FixParseError> {
+        self.validator.validate_message(&frame)?;
+        Ok(frame)
+    }
+}
+
+impl<T> Encoder<T> for FixFrameCodec
+where
+    T: AsFixMessage,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut body = Vec::new();
+        item.encode_message(&mut body)?;
+        self.builder.build_message(&mut dst.writer(), &body)
+    }
+}
+
+/// Codec decoding FIX frames as message `M`, usually `messages::Message` of a dictionary.
+///
+/// ```rust,ignore
+/// use openfix_messages::codec::FixCodec;
+/// use openfix_messages::fix44::{fields::DATA_FIELDS, messages::Message};
+/// use tokio_util::codec::Framed;
+///
+/// let codec = FixCodec::<Message>::new().with_data_fields(DATA_FIELDS);
+/// let mut framed = Framed::new(stream, codec);
+/// ```
+#[derive(Debug)]
+pub struct FixCodec<M> {
+    frames: FixFrameCodec,
+    data_fields: &'static [(u32, u32)],
+    message: PhantomData<fn() -> M>,
+}
+
+impl<M> Default for FixCodec<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> FixCodec<M> {
+    pub fn new() -> Self {
+        Self {
+            frames: FixFrameCodec::new(),
+            data_fields: &[],
+            message: PhantomData,
+        }
+    }
+
+    /// Set begin string of sent messages, and check received ones have the same
+    pub fn begin_string(mut self, value: &str) -> Self {
+        self.frames = self.frames.begin_string(value);
+        self
+    }
+
+    /// Set maximum length of received frames, see `FixFrameCodec::max_frame_length`
+    pub fn max_frame_length(mut self, value: usize) -> Self {
+        self.frames = self.frames.max_frame_length(value);
+        self
+    }
+
+    /// Set (length field ID, data field ID) pairs, usually `fields::DATA_FIELDS` of a dictionary
+    pub fn with_data_fields(mut self, data_fields: &'static [(u32, u32)]) -> Self {
+        self.data_fields = data_fields;
+        self
+    }
+}
+
+impl<M> Decoder for FixCodec<M>
+where
+    M: FromFixMessage,
+{
+    type Item = Result<M, FixParseError>;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let max_frame_length = self.frames.max_frame_length;
+        Ok(split_frame(src, max_frame_length)?.map(|frame| self.decode_frame(frame?)))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let max_frame_length = self.frames.max_frame_length;
+        Ok(split_last_frame(src, max_frame_length)?.map(|frame| self.decode_frame(frame?)))
+    }
+}
+
+impl<M> FixCodec<M>
+where
+    M: FromFixMessage,
+{
+    fn decode_frame(&self, frame: BytesMut) -> Result<M, FixParseError> {
+        let body = self.frames.validator.validate_message(&frame)?;
+        let items: FixFieldList = scan_message_items(body)
+            .with_data_fields(self.data_fields)
+            .collect();
+        M::decode_items(&items)
+    }
+}
+
+impl<M, T> Encoder<T> for FixCodec<M>
+where
+    T: AsFixMessage,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.frames.encode(item, dst)
+    }
+}
+
+/// Pop next frame from buffer, envelope is not checked
+fn split_frame(
+    src: &mut BytesMut,
+    max_frame_length: usize,
+) -> io::Result<Option<Result<BytesMut, FixParseError>>> {
+    // Body is part of frame: reject frames announcing a too large body as soon as possible
+    match find_frame_with_max_body_length(src, max_frame_length) {
+        FixFrameStatus::Complete(len) if len > max_frame_length => Err(frame_too_large()),
+        FixFrameStatus::Complete(len) => Ok(Some(Ok(src.split_to(len)))),
+        // Do not buffer more than one frame can hold
+        FixFrameStatus::Incomplete if src.len() > max_frame_length => Err(frame_too_large()),
+        FixFrameStatus::Incomplete => Ok(None),
+        FixFrameStatus::Invalid(skipped) => match find_frame(src) {
+            FixFrameStatus::Invalid(_) => {
+                src.advance(skipped);
+                Ok(Some(Err(FixParseError::InvalidFrame { skipped })))
+            }
+            // Frame is only invalid because of its body length
+            _ => Err(frame_too_large()),
+        },
+    }
+}
+
+/// Same as `split_frame`, but drop remaining bytes since stream is closed
+fn split_last_frame(
+    src: &mut BytesMut,
+    max_frame_length: usize,
+) -> io::Result<Option<Result<BytesMut, FixParseError>>> {
+    match split_frame(src, max_frame_length)? {
+        Some(frame) => Ok(Some(frame)),
+        None if !src.is_empty() => {
+            let skipped = src.len();
+            src.clear();
+            Ok(Some(Err(FixParseError::InvalidFrame { skipped })))
+        }
+        None => Ok(None),
+    }
+}
+
+fn frame_too_large() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "frame length exceeds max_frame_length",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEARTBEAT: &[u8] = b"8=FIX.4.4\x019=5\x0135=0\x0110=163\x01";
+
+    fn decode_all(codec: &mut FixFrameCodec, data: &[u8]) -> Vec<Result<BytesMut, FixParseError>> {
+        let mut src = BytesMut::from(data);
+        let mut output = Vec::new();
+        while let Some(item) = codec.decode(&mut src).unwrap() {
+            output.push(item);
+        }
+        output
+    }
+
+    #[test]
+    fn test_decode_frames() {
+        let mut codec = FixFrameCodec::new();
+
+        let mut data = HEARTBEAT.to_vec();
+        data.extend_from_slice(HEARTBEAT);
+        assert_eq!(
+            decode_all(&mut codec, &data),
+            vec![Ok(BytesMut::from(HEARTBEAT)), Ok(BytesMut::from(HEARTBEAT))]
+        );
+    }
+
+    #[test]
+    fn test_decode_partial_frame() {
+        let mut codec = FixFrameCodec::new();
+
+        let mut src = BytesMut::from(&HEARTBEAT[..10]);
+        assert_eq!(codec.decode(&mut src).unwrap(), None);
+        assert_eq!(src.len(), 10);
+
+        src.extend_from_slice(&HEARTBEAT[10..]);
+        assert_eq!(
+            codec.decode(&mut src).unwrap(),
+            Some(Ok(BytesMut::from(HEARTBEAT)))
+        );
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn test_decode_invalid_frames() {
+        let mut codec = FixFrameCodec::new().begin_string("FIX.4.2");
+
+        let mut data = b"garbage\x01".to_vec();
+        data.extend_from_slice(HEARTBEAT);
+        data.extend_from_slice(b"8=FIX.4.2\x019=5\x0135=0\x0110=000\x01");
+        assert_eq!(
+            decode_all(&mut codec, &data),
+            vec![
+                Err(FixParseError::InvalidFrame { skipped: 8 }),
+                Err(FixParseError::InvalidBeginString),
+                Err(FixParseError::InvalidCheckSum {
+                    declared: 0,
+                    computed: 161
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_eof() {
+        let mut codec = FixFrameCodec::new();
+
+        let mut src = BytesMut::from(&HEARTBEAT[..10]);
+        assert_eq!(
+            codec.decode_eof(&mut src).unwrap(),
+            Some(Err(FixParseError::InvalidFrame { skipped: 10 }))
+        );
+        assert_eq!(codec.decode_eof(&mut src).unwrap(), None);
+    }
+
+    #[test]
+    fn test_decode_body_length_overflow() {
+        let mut codec = FixFrameCodec::new();
+
+        let mut data = b"8=FIX.4.4\x019=18446744073709551600\x0135=0\x01".to_vec();
+        data.extend_from_slice(HEARTBEAT);
+        assert_eq!(
+            decode_all(&mut codec, &data),
+            vec![
+                Err(FixParseError::InvalidFrame { skipped: 38 }),
+                Ok(BytesMut::from(HEARTBEAT)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_max_frame_length() {
+        let mut codec = FixFrameCodec::new().max_frame_length(HEARTBEAT.len());
+        assert_eq!(
+            decode_all(&mut codec, HEARTBEAT),
+            vec![Ok(BytesMut::from(HEARTBEAT))]
+        );
+
+        // Complete frame too large
+        let mut codec = FixFrameCodec::new().max_frame_length(HEARTBEAT.len() - 1);
+        let mut src = BytesMut::from(HEARTBEAT);
+        let err = codec.decode(&mut src).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Declared body length too large, as soon as it is read
+        let mut codec = FixFrameCodec::new().max_frame_length(1000);
+        let mut src = BytesMut::from(&b"8=FIX.4.4\x019=999999999\x01"[..]);
+        let err = codec.decode(&mut src).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), frame_too_large().to_string());
+
+        // Incomplete frame already too large to be buffered
+        let mut codec = FixFrameCodec::new().max_frame_length(20);
+        let mut src = BytesMut::from(&b"8=FIX.4.4\x019=10\x01"[..]);
+        assert_eq!(codec.decode(&mut src).unwrap(), None);
+        src.extend_from_slice(b"35=0\x01123=Y\x01");
+        let err = codec.decode(&mut src).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
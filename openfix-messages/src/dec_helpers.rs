@@ -1,32 +1,582 @@
 use std::collections::HashMap;
+use std::ops::Range;
 
+use crate::enc_helpers::compute_check_sum;
+use crate::field_types::FixFieldValue;
+use crate::{FixFieldContext, FixGroupEntry, FixParseError};
+
+/// Map of (key ID => value) data.
+///
+/// This is a convenience view: field order is lost and repeated tags only
+/// keep their last value. Use `scan_message_items` when order matters.
 pub type FixFieldItems<'a> = HashMap<u32, &'a [u8]>;
 
+/// Ordered list of message items, as read on the wire.
+pub type FixFieldList<'a> = Vec<FixFieldItem<'a>>;
+
 const SEP_CHAR: u8 = 0x01;
 
-pub fn split_message_items<'a>(data: &'a [u8]) -> FixFieldItems<'a> {
-    data.split(|x| *x == SEP_CHAR)
-        .filter_map(|field| {
-            let mut fields = field.splitn(2, |x| *x == '=' as u8);
+const TAG_BEGIN_STRING: &[u8] = b"8=";
+const TAG_BODY_LENGTH: &[u8] = b"9=";
+const TAG_MSG_TYPE: &[u8] = b"35=";
+const TAG_CHECK_SUM: &[u8] = b"10=";
+
+/// BeginString, BodyLength and CheckSum field IDs
+const ENVELOPE_TAGS: &[u32] = &[8, 9, 10];
+
+/// Single "Key=Value" item of a FIX message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixFieldItem<'a> {
+    /// Field ID
+    pub tag: u32,
+    /// Raw field value
+    pub value: &'a [u8],
+    /// Offset of the first tag byte in the scanned buffer
+    pub offset: usize,
+}
+
+/// Owned field kept on decoded messages when its tag is not part of the dictionary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixExtraField {
+    /// Field ID
+    pub tag: u32,
+    /// Raw field value
+    pub value: Vec<u8>,
+}
+
+impl FixExtraField {
+    pub fn new(tag: u32, value: &[u8]) -> Self {
+        Self {
+            tag,
+            value: value.to_vec(),
+        }
+    }
+}
+
+impl From<&FixFieldItem<'_>> for FixExtraField {
+    fn from(item: &FixFieldItem<'_>) -> Self {
+        Self::new(item.tag, item.value)
+    }
+}
+
+/// Iterator over message items.
+///
+/// Items are returned in wire order and duplicated tags are kept, which is
+/// required to decode repeating groups.
+/// Malformed items are skipped the same way `split_message_items` does.
+///
+/// Data fields (which may contain SOH) are only read correctly when their
+/// (length field ID, data field ID) pairs are given with `with_data_fields`.
+#[derive(Debug, Clone)]
+pub struct FixFieldScanner<'a> {
+    data: &'a [u8],
+    position: usize,
+    data_fields: &'a [(u32, u32)],
+    /// Data field ID and length expected for next item
+    pending_data: Option<(u32, usize)>,
+}
+
+impl<'a> FixFieldScanner<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            data_fields: &[],
+            pending_data: None,
+        }
+    }
+
+    /// Set (length field ID, data field ID) pairs, usually `fields::DATA_FIELDS` of a dictionary
+    pub fn with_data_fields(mut self, data_fields: &'a [(u32, u32)]) -> Self {
+        self.data_fields = data_fields;
+        self
+    }
 
-            let field_id = fields.next()?;
-            let field_id = std::str::from_utf8(field_id).ok()?;
-            let field_id = field_id.parse::<u32>().ok()?;
+    fn parse_item(&self, start: usize, end: usize) -> Option<FixFieldItem<'a>> {
+        let field = &self.data[start..end];
+        let mut fields = field.splitn(2, |x| *x == b'=');
 
-            let field_data = fields.next()?;
+        let tag = fields.next()?;
+        let tag = std::str::from_utf8(tag).ok()?;
+        let tag = tag.parse::<u32>().ok()?;
 
-            Some((field_id, field_data))
+        let value = fields.next()?;
+
+        Some(FixFieldItem {
+            tag,
+            value,
+            offset: start,
         })
+    }
+
+    /// Get end of data field starting at given position, when it follows its length field
+    fn data_item_end(&self, start: usize) -> Option<usize> {
+        let (data_tag, length) = self.pending_data?;
+
+        let equal = start + self.data[start..].iter().position(|x| *x == b'=')?;
+        let tag: u32 = std::str::from_utf8(&self.data[start..equal])
+            .ok()?
+            .parse()
+            .ok()?;
+        if tag != data_tag {
+            return None;
+        }
+
+        // Declared length is untrusted, it may not fit in the buffer
+        let end = (equal + 1).checked_add(length)?;
+        match self.data.get(end) {
+            Some(&SEP_CHAR) => Some(end),
+            None if end == self.data.len() => Some(end),
+            _ => None,
+        }
+    }
+
+    /// Get data field ID and length announced by given item, if it is a length field
+    fn announced_data(&self, item: &FixFieldItem) -> Option<(u32, usize)> {
+        let (_, data_tag) = self.data_fields.iter().find(|(x, _)| *x == item.tag)?;
+        let length = usize::read_fix_value(item.value)?;
+        Some((*data_tag, length))
+    }
+}
+
+impl<'a> Iterator for FixFieldScanner<'a> {
+    type Item = FixFieldItem<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.position < self.data.len() {
+            let start = self.position;
+            let end = self.data_item_end(start).unwrap_or_else(|| {
+                self.data[start..]
+                    .iter()
+                    .position(|x| *x == SEP_CHAR)
+                    .map_or(self.data.len(), |x| start + x)
+            });
+
+            self.position = end + 1;
+
+            let item = self.parse_item(start, end);
+            self.pending_data = item.as_ref().and_then(|x| self.announced_data(x));
+
+            if let Some(item) = item {
+                return Some(item);
+            }
+        }
+
+        None
+    }
+}
+
+pub fn scan_message_items(data: &[u8]) -> FixFieldScanner<'_> {
+    FixFieldScanner::new(data)
+}
+
+pub fn split_message_items(data: &[u8]) -> FixFieldItems<'_> {
+    scan_message_items(data)
+        .map(|item| (item.tag, item.value))
         .collect()
 }
 
+/// Collect fields that are not part of any known tag list, in wire order.
+///
+/// Envelope fields (BeginString, BodyLength and CheckSum) are never collected,
+/// since they are written back by `FixEnvelopeBuilder`. Neither are items
+/// within `group_ranges`, which are kept by group entries.
+pub fn decode_extra_fields(
+    items: &[FixFieldItem],
+    known_tags: &[&[u32]],
+    group_ranges: &[Range<usize>],
+) -> Vec<FixExtraField> {
+    items
+        .iter()
+        .enumerate()
+        .filter(|(position, _)| !group_ranges.iter().any(|x| x.contains(position)))
+        .map(|(_, item)| item)
+        .filter(|x| !ENVELOPE_TAGS.contains(&x.tag))
+        .filter(|x| !known_tags.iter().any(|tags| tags.contains(&x.tag)))
+        .map(FixExtraField::from)
+        .collect()
+}
+
+/// Decode repeating group starting at its count field.
+///
+/// Count field drives the number of entries to read and each entry must
+/// start with the group delimiter field. An entry stops at the next delimiter
+/// or at the first dictionary field that is not part of the group: fields
+/// missing from dictionary are kept in the entry.
+///
+/// Return `None` when the count field is not present.
+pub fn decode_group<T>(
+    items: &[FixFieldItem],
+    count_key: u32,
+    count_name: &'static str,
+) -> Result<Option<Vec<T>>, FixParseError>
+where
+    T: FixGroupEntry,
+{
+    let entries = match split_group::<T>(items, count_key, count_name)? {
+        Some((_, entries)) => entries,
+        None => return Ok(None),
+    };
+
+    entries
+        .into_iter()
+        .map(T::decode_items)
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+/// Get range of items covered by repeating group, from its count field to its last entry.
+///
+/// Return `None` when the count field is not present or group cannot be decoded.
+pub fn group_range<T>(
+    items: &[FixFieldItem],
+    count_key: u32,
+    count_name: &'static str,
+) -> Option<Range<usize>>
+where
+    T: FixGroupEntry,
+{
+    split_group::<T>(items, count_key, count_name)
+        .ok()
+        .flatten()
+        .map(|(range, _)| range)
+}
+
+/// Split repeating group into entries, see `decode_group`.
+///
+/// Return range of items covered by the group, with items of each entry.
+#[allow(clippy::type_complexity)]
+fn split_group<'a, 'b, T>(
+    items: &'b [FixFieldItem<'a>],
+    count_key: u32,
+    count_name: &'static str,
+) -> Result<Option<(Range<usize>, Vec<&'b [FixFieldItem<'a>]>)>, FixParseError>
+where
+    T: FixGroupEntry,
+{
+    let position = match items.iter().position(|x| x.tag == count_key) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    let count_value = items[position].value;
+    let context = FixFieldContext::new(count_key, count_name).with_value(count_value);
+
+    let count = u32::read_fix_value(count_value)
+        .ok_or_else(|| FixParseError::InvalidFormat(context.clone()))? as usize;
+
+    let mut remaining = &items[position + 1..];
+
+    // Each entry holds at least its delimiter field: reject count sent by peer
+    // before using it, since it may be huge
+    if count > remaining.len() {
+        return Err(FixParseError::InvalidGroupCount {
+            context,
+            declared: count,
+            actual: remaining.iter().filter(|x| x.tag == T::DELIMITER).count(),
+        });
+    }
+
+    let is_entry_end = |item: &FixFieldItem| {
+        item.tag == T::DELIMITER
+            || (!T::FIX_TAGS.contains(&item.tag)
+                && T::DICTIONARY_TAGS.binary_search(&item.tag).is_ok())
+    };
+
+    let mut entries = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        match remaining.first() {
+            Some(item) if item.tag == T::DELIMITER => {}
+            _ => {
+                return Err(FixParseError::InvalidGroupCount {
+                    context,
+                    declared: count,
+                    actual: entries.len(),
+                })
+            }
+        }
+
+        let entry_len = remaining
+            .iter()
+            .skip(1)
+            .position(is_entry_end)
+            .map_or(remaining.len(), |x| x + 1);
+
+        entries.push(&remaining[..entry_len]);
+        remaining = &remaining[entry_len..];
+    }
+
+    let end = items.len() - remaining.len();
+    Ok(Some((position..end, entries)))
+}
+
+/// This class check standard fields added by `FixEnvelopeBuilder` on a
+/// received message. Including:
+/// - begin string (any value by default)
+/// - length
+/// - check sum
+///
+/// It also check that begin string, length and message type are the first
+/// fields of the message, in this order.
+#[derive(Debug, Default)]
+pub struct FixEnvelopeValidator {
+    begin_string: Option<String>,
+}
+
+impl FixEnvelopeValidator {
+    pub fn new() -> Self {
+        Self { begin_string: None }
+    }
+
+    pub fn begin_string(mut self, value: &str) -> Self {
+        self.begin_string = Some(value.into());
+        self
+    }
+
+    /// Validate message envelope and return message body.
+    ///
+    /// Body starts at message type field and stops before check sum field,
+    /// which is the data `FixEnvelopeBuilder::build_message` is called with.
+    pub fn validate_message<'a>(&self, data: &'a [u8]) -> Result<&'a [u8], FixParseError> {
+        let (begin_string, remaining) =
+            split_leading_field(data, TAG_BEGIN_STRING).ok_or(FixParseError::MissingBeginString)?;
+
+        if let Some(expected) = &self.begin_string {
+            if begin_string != expected.as_bytes() {
+                return Err(FixParseError::InvalidBeginString);
+            }
+        }
+
+        let (body_length, body) = split_leading_field(remaining, TAG_BODY_LENGTH)
+            .ok_or(FixParseError::MissingBodyLength)?;
+        let body_length =
+            usize::read_fix_value(body_length).ok_or(FixParseError::MissingBodyLength)?;
+
+        if !body.starts_with(TAG_MSG_TYPE) {
+            return Err(FixParseError::MissingMsgType);
+        }
+
+        // Check sum is the last field: look for its start from message end
+        if body.last() != Some(&SEP_CHAR) {
+            return Err(FixParseError::MissingCheckSum);
+        }
+        let check_sum_start = body[..body.len() - 1]
+            .iter()
+            .rposition(|x| *x == SEP_CHAR)
+            .map_or(0, |x| x + 1);
+        let (body, check_sum_field) = body.split_at(check_sum_start);
+
+        let declared = check_sum_field[..check_sum_field.len() - 1]
+            .strip_prefix(TAG_CHECK_SUM)
+            .filter(|x| x.len() == 3 && x.iter().all(u8::is_ascii_digit))
+            .ok_or(FixParseError::MissingCheckSum)?;
+        let declared: u16 = std::str::from_utf8(declared)?
+            .parse()
+            .map_err(|_e| FixParseError::MissingCheckSum)?;
+
+        if body.len() != body_length {
+            return Err(FixParseError::InvalidBodyLength {
+                declared: body_length,
+                actual: body.len(),
+            });
+        }
+
+        let computed = compute_check_sum(&data[..data.len() - check_sum_field.len()]);
+        if declared != u16::from(computed) {
+            return Err(FixParseError::InvalidCheckSum { declared, computed });
+        }
+
+        Ok(body)
+    }
+}
+
+/// Split "Key=Value\x01" first field of data when it has given tag.
+/// Return field value and remaining data.
+fn split_leading_field<'a>(data: &'a [u8], tag: &[u8]) -> Option<(&'a [u8], &'a [u8])> {
+    let data = data.strip_prefix(tag)?;
+    let end = data.iter().position(|x| *x == SEP_CHAR)?;
+    Some((&data[..end], &data[end + 1..]))
+}
+
+/// Result of looking for a FIX frame at the start of a buffer.
+#[derive(Debug, PartialEq)]
+pub enum FixFrameStatus {
+    /// Buffer starts with a complete frame of given length
+    Complete(usize),
+    /// More bytes are required to read next frame
+    Incomplete,
+    /// Buffer starts with garbage: given bytes count must be skipped to
+    /// resync on next "8="
+    Invalid(usize),
+}
+
+/// Look for a complete FIX frame at the start of data.
+///
+/// Frame length is read from body length field, and frame must end with
+/// "10=xxx\x01". Check sum value itself is not verified here, see
+/// `FixEnvelopeValidator` for this.
+pub fn find_frame(data: &[u8]) -> FixFrameStatus {
+    find_frame_with_max_body_length(data, usize::MAX)
+}
+
+/// Same as `find_frame`, but frames declaring a body length above given maximum
+/// are invalid, so that a peer cannot make caller buffer data without limit.
+pub fn find_frame_with_max_body_length(data: &[u8], max_body_length: usize) -> FixFrameStatus {
+    macro_rules! need_bytes {
+        ($len:expr) => {
+            if data.len() < $len {
+                return FixFrameStatus::Incomplete;
+            }
+        };
+    }
+
+    let invalid = || FixFrameStatus::Invalid(next_frame_start(data));
+
+    need_bytes!(TAG_BEGIN_STRING.len());
+    if !data.starts_with(TAG_BEGIN_STRING) {
+        return invalid();
+    }
+
+    let begin_string_end = match data.iter().position(|x| *x == SEP_CHAR) {
+        Some(position) => position,
+        None => return FixFrameStatus::Incomplete,
+    };
+
+    let body_length_start = begin_string_end + 1;
+    need_bytes!(body_length_start + TAG_BODY_LENGTH.len());
+    if !data[body_length_start..].starts_with(TAG_BODY_LENGTH) {
+        return invalid();
+    }
+
+    let body_length_start = body_length_start + TAG_BODY_LENGTH.len();
+    let body_length_end = match data[body_length_start..]
+        .iter()
+        .position(|x| !x.is_ascii_digit())
+    {
+        Some(position) => body_length_start + position,
+        None => return FixFrameStatus::Incomplete,
+    };
+    if data[body_length_end] != SEP_CHAR {
+        return invalid();
+    }
+
+    let body_length = usize::read_fix_value(&data[body_length_start..body_length_end]);
+    let body_length = match body_length {
+        Some(body_length) if body_length <= max_body_length => body_length,
+        _ => return invalid(),
+    };
+
+    // Body length is sent by peer: it may overflow
+    let frame_end = (body_length_end + 1)
+        .checked_add(body_length)
+        .and_then(|x| x.checked_add(TAG_CHECK_SUM.len() + 4));
+    let frame_end = match frame_end {
+        Some(frame_end) => frame_end,
+        None => return invalid(),
+    };
+    let check_sum_start = frame_end - TAG_CHECK_SUM.len() - 4;
+    need_bytes!(frame_end);
+
+    let check_sum = &data[check_sum_start..frame_end];
+    if check_sum.starts_with(TAG_CHECK_SUM)
+        && check_sum[TAG_CHECK_SUM.len()..frame_end - check_sum_start - 1]
+            .iter()
+            .all(u8::is_ascii_digit)
+        && check_sum.last() == Some(&SEP_CHAR)
+    {
+        FixFrameStatus::Complete(frame_end)
+    } else {
+        invalid()
+    }
+}
+
+/// Get position of next "8=" field after data first byte.
+///
+/// Trailing "8" is kept since it may be the start of next frame.
+fn next_frame_start(data: &[u8]) -> usize {
+    let pattern = [SEP_CHAR, TAG_BEGIN_STRING[0], TAG_BEGIN_STRING[1]];
+
+    match data.windows(pattern.len()).position(|x| x == pattern) {
+        Some(position) => position + 1,
+        None if data.len() > 1 && data.ends_with(&pattern[..2]) => data.len() - 1,
+        None => data.len(),
+    }
+}
+
+/// Default maximum body length of frames read by `FixFrameDecoder`
+pub const DEFAULT_MAX_BODY_LENGTH: usize = 8 * 1024 * 1024;
+
+/// This class split a stream of bytes (as read from a socket) into complete
+/// FIX frames.
+///
+/// Data can be appended by chunks of any size. Frames are then returned one
+/// at a time, in the order they have been received.
+///
+/// Frames declaring a body length above `DEFAULT_MAX_BODY_LENGTH` (which can be
+/// changed with `max_body_length`) are dropped as garbage.
+#[derive(Debug)]
+pub struct FixFrameDecoder {
+    buffer: Vec<u8>,
+    max_body_length: usize,
+}
+
+impl Default for FixFrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FixFrameDecoder {
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            max_body_length: DEFAULT_MAX_BODY_LENGTH,
+        }
+    }
+
+    /// Set maximum body length of accepted frames
+    pub fn max_body_length(mut self, value: usize) -> Self {
+        self.max_body_length = value;
+        self
+    }
+
+    /// Append received bytes to decoder buffer
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Get count of buffered bytes not returned yet
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Pop next complete frame from buffer.
+    ///
+    /// Return:
+    /// - `Ok(Some(frame))` when a complete frame is available
+    /// - `Ok(None)` when more bytes are required
+    /// - `Err(FixParseError::InvalidFrame)` when garbage has been dropped from
+    ///   buffer. Decoding can continue with next call.
+    pub fn next_frame(&mut self) -> Result<Option<Vec<u8>>, FixParseError> {
+        match find_frame_with_max_body_length(&self.buffer, self.max_body_length) {
+            FixFrameStatus::Complete(len) => Ok(Some(self.buffer.drain(..len).collect())),
+            FixFrameStatus::Incomplete => Ok(None),
+            FixFrameStatus::Invalid(skipped) => {
+                self.buffer.drain(..skipped);
+                Err(FixParseError::InvalidFrame { skipped })
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::array::IntoIter;
+    use crate::enc_helpers::FixEnvelopeBuilder;
     use std::iter::FromIterator;
 
-    // rustc v1.51 required to run this tests
+    // rustc v1.53 required to run this tests
 
     macro_rules! split_non_static {
         ($x:expr) => {{
@@ -44,30 +594,30 @@ mod tests {
     fn test_split_message_items_std_payload() {
         assert_eq!(
             split_non_static!(b""),
-            HashMap::from_iter(IntoIter::new([]))
+            HashMap::from_iter(IntoIterator::into_iter([]))
         );
         assert_eq!(
             split_non_static!(b"\x01"),
-            HashMap::from_iter(IntoIter::new([]))
+            HashMap::from_iter(IntoIterator::into_iter([]))
         );
         assert_eq!(
             split_non_static!(b"\x01\x01\x01\x01"),
-            HashMap::from_iter(IntoIter::new([]))
+            HashMap::from_iter(IntoIterator::into_iter([]))
         );
         assert_eq!(
             split_non_static!(b"5=foo"),
-            HashMap::from_iter(IntoIter::new([(5, b"foo".to_vec())]))
+            HashMap::from_iter(IntoIterator::into_iter([(5, b"foo".to_vec())]))
         );
         assert_eq!(
             split_non_static!(b"5=foo\x012631=bar"),
-            HashMap::from_iter(IntoIter::new([
+            HashMap::from_iter(IntoIterator::into_iter([
                 (5, b"foo".to_vec()),
                 (2631, b"bar".to_vec())
             ]))
         );
         assert_eq!(
             split_non_static!(b"\x01\x01\x015=foo\x012631=bar\x01\x01\x01"),
-            HashMap::from_iter(IntoIter::new([
+            HashMap::from_iter(IntoIterator::into_iter([
                 (5, b"foo".to_vec()),
                 (2631, b"bar".to_vec())
             ]))
@@ -78,15 +628,428 @@ mod tests {
     fn test_split_message_items_weird_payload() {
         assert_eq!(
             split_non_static!(b"5="),
-            HashMap::from_iter(IntoIter::new([(5, b"".to_vec())]))
+            HashMap::from_iter(IntoIterator::into_iter([(5, b"".to_vec())]))
         );
         assert_eq!(
             split_non_static!(b"foo=bar"),
-            HashMap::from_iter(IntoIter::new([]))
+            HashMap::from_iter(IntoIterator::into_iter([]))
         );
         assert_eq!(
             split_non_static!(b"foobar"),
-            HashMap::from_iter(IntoIter::new([]))
+            HashMap::from_iter(IntoIterator::into_iter([]))
+        );
+    }
+
+    macro_rules! scan_non_static {
+        ($x:expr) => {{
+            scan_non_static!($x, &[])
+        }};
+        ($x:expr, $data_fields:expr) => {{
+            let result: Vec<(u32, Vec<u8>, usize)> = scan_message_items($x)
+                .with_data_fields($data_fields)
+                .map(|item| (item.tag, item.value.to_vec(), item.offset))
+                .collect();
+
+            result
+        }};
+    }
+
+    macro_rules! build_message {
+        ($content:expr) => {{
+            let mut data = Vec::new();
+            FixEnvelopeBuilder::new()
+                .build_message(&mut data, $content)
+                .unwrap();
+            data
+        }};
+    }
+
+    #[test]
+    fn test_validate_envelope() {
+        let validator = FixEnvelopeValidator::new();
+
+        assert_eq!(
+            validator.validate_message(b"8=FIX.4.4\x019=12\x0135=0\x0149=bar\x0110=177\x01"),
+            Ok(&b"35=0\x0149=bar\x01"[..])
+        );
+
+        let data = build_message!(b"35=A\x0149=SERVER\x0156=CLIENT\x0134=177\x0152=20090107-18:15:16\x0198=0\x01108=30\x01");
+        assert_eq!(
+            validator.validate_message(&data),
+            Ok(&b"35=A\x0149=SERVER\x0156=CLIENT\x0134=177\x0152=20090107-18:15:16\x0198=0\x01108=30\x01"[..])
+        );
+    }
+
+    #[test]
+    fn test_validate_envelope_begin_string() {
+        let data = build_message!(b"35=0\x0149=bar\x01");
+
+        assert!(FixEnvelopeValidator::new()
+            .begin_string("FIX.4.4")
+            .validate_message(&data)
+            .is_ok());
+        assert_eq!(
+            FixEnvelopeValidator::new()
+                .begin_string("FIX.4.2")
+                .validate_message(&data),
+            Err(FixParseError::InvalidBeginString)
+        );
+    }
+
+    #[test]
+    fn test_validate_envelope_field_order() {
+        let validator = FixEnvelopeValidator::new();
+
+        assert_eq!(
+            validator.validate_message(b""),
+            Err(FixParseError::MissingBeginString)
+        );
+        assert_eq!(
+            validator.validate_message(b"9=12\x018=FIX.4.4\x0135=0\x0149=bar\x0110=177\x01"),
+            Err(FixParseError::MissingBeginString)
+        );
+        assert_eq!(
+            validator.validate_message(b"8=FIX.4.4\x0135=0\x019=12\x0149=bar\x0110=177\x01"),
+            Err(FixParseError::MissingBodyLength)
+        );
+        assert_eq!(
+            validator.validate_message(b"8=FIX.4.4\x019=abc\x0135=0\x0149=bar\x0110=177\x01"),
+            Err(FixParseError::MissingBodyLength)
+        );
+        assert_eq!(
+            validator.validate_message(b"8=FIX.4.4\x019=12\x0149=bar\x0135=0\x0110=177\x01"),
+            Err(FixParseError::MissingMsgType)
+        );
+    }
+
+    #[test]
+    fn test_validate_envelope_check_sum_field() {
+        let validator = FixEnvelopeValidator::new();
+
+        assert_eq!(
+            validator.validate_message(b"8=FIX.4.4\x019=12\x0135=0\x0149=bar\x01"),
+            Err(FixParseError::MissingCheckSum)
+        );
+        assert_eq!(
+            validator.validate_message(b"8=FIX.4.4\x019=12\x0135=0\x0149=bar\x0110=177"),
+            Err(FixParseError::MissingCheckSum)
+        );
+        assert_eq!(
+            validator.validate_message(b"8=FIX.4.4\x019=12\x0135=0\x0149=bar\x0110=13\x01"),
+            Err(FixParseError::MissingCheckSum)
+        );
+        assert_eq!(
+            validator.validate_message(b"8=FIX.4.4\x019=12\x0135=0\x0149=bar\x0110=177\x01\x01"),
+            Err(FixParseError::MissingCheckSum)
+        );
+    }
+
+    #[test]
+    fn test_validate_envelope_body_length() {
+        let validator = FixEnvelopeValidator::new();
+
+        assert_eq!(
+            validator.validate_message(b"8=FIX.4.4\x019=15\x0135=0\x0149=bar\x0110=177\x01"),
+            Err(FixParseError::InvalidBodyLength {
+                declared: 15,
+                actual: 12
+            })
+        );
+        assert_eq!(
+            validator.validate_message(b"8=FIX.4.4\x019=12\x0135=0\x0149=ba\x0110=177\x01"),
+            Err(FixParseError::InvalidBodyLength {
+                declared: 12,
+                actual: 11
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_envelope_check_sum() {
+        let validator = FixEnvelopeValidator::new();
+
+        assert_eq!(
+            validator.validate_message(b"8=FIX.4.4\x019=12\x0135=0\x0149=baz\x0110=177\x01"),
+            Err(FixParseError::InvalidCheckSum {
+                declared: 177,
+                computed: 185
+            })
+        );
+        assert_eq!(
+            validator.validate_message(b"8=FIX.4.4\x019=12\x0135=0\x0149=bar\x0110=999\x01"),
+            Err(FixParseError::InvalidCheckSum {
+                declared: 999,
+                computed: 177
+            })
+        );
+    }
+
+    const FRAME_1: &[u8] = b"8=FIX.4.4\x019=12\x0135=0\x0149=bar\x0110=177\x01";
+    const FRAME_2: &[u8] = b"8=FIX.4.2\x019=5\x0135=1\x0110=042\x01";
+
+    #[test]
+    fn test_find_frame_complete() {
+        assert_eq!(find_frame(FRAME_1), FixFrameStatus::Complete(FRAME_1.len()));
+        assert_eq!(find_frame(FRAME_2), FixFrameStatus::Complete(FRAME_2.len()));
+
+        let data = [FRAME_1, FRAME_2].concat();
+        assert_eq!(find_frame(&data), FixFrameStatus::Complete(FRAME_1.len()));
+
+        // Frame content is not inspected
+        assert_eq!(
+            find_frame(b"8=FIX.4.4\x019=5\x01\x01\x01\x01\x01\x0110=999\x01"),
+            FixFrameStatus::Complete(26)
+        );
+    }
+
+    #[test]
+    fn test_find_frame_incomplete() {
+        for len in 0..FRAME_1.len() {
+            assert_eq!(
+                find_frame(&FRAME_1[..len]),
+                FixFrameStatus::Incomplete,
+                "len = {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_frame_invalid() {
+        assert_eq!(find_frame(b"foo"), FixFrameStatus::Invalid(3));
+        assert_eq!(find_frame(b"foo\x018"), FixFrameStatus::Invalid(4));
+        assert_eq!(find_frame(b"58=foo\x018=FIX"), FixFrameStatus::Invalid(7));
+        assert_eq!(
+            find_frame(b"8=FIX.4.4\x0135=0\x01"),
+            FixFrameStatus::Invalid(15)
+        );
+        assert_eq!(
+            find_frame(b"8=FIX.4.4\x019=1a\x018=FIX"),
+            FixFrameStatus::Invalid(15)
+        );
+        // Body length does not match check sum position
+        assert_eq!(
+            find_frame(b"8=FIX.4.4\x019=10\x0135=0\x0149=bar\x0110=177\x01"),
+            FixFrameStatus::Invalid(34)
+        );
+        assert_eq!(
+            find_frame(
+                &[
+                    &b"8=FIX.4.4\x019=14\x0135=0\x0149=bar\x0110=177\x01"[..],
+                    FRAME_2
+                ]
+                .concat()
+            ),
+            FixFrameStatus::Invalid(34)
+        );
+    }
+
+    #[test]
+    fn test_find_frame_body_length_overflow() {
+        let data = b"8=FIX.4.4\x019=18446744073709551600\x0135=0\x01";
+        assert_eq!(find_frame(data), FixFrameStatus::Invalid(data.len()));
+    }
+
+    #[test]
+    fn test_find_frame_max_body_length() {
+        assert_eq!(
+            find_frame_with_max_body_length(FRAME_1, 12),
+            FixFrameStatus::Complete(FRAME_1.len())
+        );
+        assert_eq!(
+            find_frame_with_max_body_length(FRAME_1, 11),
+            FixFrameStatus::Invalid(FRAME_1.len())
+        );
+        assert_eq!(
+            find_frame_with_max_body_length(b"8=FIX.4.4\x019=100000\x0135=0\x01", 1000),
+            FixFrameStatus::Invalid(24)
+        );
+    }
+
+    #[test]
+    fn test_frame_decoder_partial_reads() {
+        let data = [FRAME_1, FRAME_2, FRAME_1].concat();
+
+        for chunk_size in 1..data.len() {
+            let mut decoder = FixFrameDecoder::new();
+            let mut frames = Vec::new();
+
+            for chunk in data.chunks(chunk_size) {
+                decoder.extend_from_slice(chunk);
+                while let Some(frame) = decoder.next_frame().unwrap() {
+                    frames.push(frame);
+                }
+            }
+
+            assert_eq!(frames, vec![FRAME_1, FRAME_2, FRAME_1]);
+            assert_eq!(decoder.buffered_len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_frame_decoder_resync() {
+        let mut decoder = FixFrameDecoder::new();
+        decoder.extend_from_slice(b"garbage\x01");
+        decoder.extend_from_slice(FRAME_1);
+        decoder.extend_from_slice(b"8=FIX.4.4\x019=xx\x01");
+        decoder.extend_from_slice(&FRAME_2[..10]);
+
+        assert_eq!(
+            decoder.next_frame(),
+            Err(FixParseError::InvalidFrame { skipped: 8 })
+        );
+        assert_eq!(decoder.next_frame(), Ok(Some(FRAME_1.to_vec())));
+        assert_eq!(
+            decoder.next_frame(),
+            Err(FixParseError::InvalidFrame { skipped: 15 })
+        );
+        assert_eq!(decoder.next_frame(), Ok(None));
+
+        decoder.extend_from_slice(&FRAME_2[10..]);
+        assert_eq!(decoder.next_frame(), Ok(Some(FRAME_2.to_vec())));
+        assert_eq!(decoder.next_frame(), Ok(None));
+    }
+
+    #[test]
+    fn test_frame_decoder_max_body_length() {
+        let mut decoder = FixFrameDecoder::new().max_body_length(8);
+        decoder.extend_from_slice(b"8=FIX.4.4\x019=100000\x0135=0\x01");
+        decoder.extend_from_slice(FRAME_2);
+
+        assert_eq!(
+            decoder.next_frame(),
+            Err(FixParseError::InvalidFrame { skipped: 24 })
+        );
+        assert_eq!(decoder.next_frame(), Ok(Some(FRAME_2.to_vec())));
+        assert_eq!(decoder.buffered_len(), 0);
+    }
+
+    #[test]
+    fn test_scan_message_items_std_payload() {
+        assert_eq!(scan_non_static!(b""), vec![]);
+        assert_eq!(scan_non_static!(b"\x01\x01\x01\x01"), vec![]);
+        assert_eq!(scan_non_static!(b"5=foo"), vec![(5, b"foo".to_vec(), 0)]);
+        assert_eq!(
+            scan_non_static!(b"\x01\x015=foo\x012631=bar\x01\x01"),
+            vec![(5, b"foo".to_vec(), 2), (2631, b"bar".to_vec(), 8)]
+        );
+    }
+
+    #[test]
+    fn test_scan_message_items_keep_order_and_duplicates() {
+        assert_eq!(
+            scan_non_static!(b"453=2\x01448=A\x01452=1\x01448=B\x01452=3\x01"),
+            vec![
+                (453, b"2".to_vec(), 0),
+                (448, b"A".to_vec(), 6),
+                (452, b"1".to_vec(), 12),
+                (448, b"B".to_vec(), 18),
+                (452, b"3".to_vec(), 24),
+            ]
+        );
+        assert_eq!(
+            split_non_static!(b"453=2\x01448=A\x01452=1\x01448=B\x01452=3\x01"),
+            HashMap::from_iter([
+                (453, b"2".to_vec()),
+                (448, b"B".to_vec()),
+                (452, b"3".to_vec()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_scan_message_items_weird_payload() {
+        assert_eq!(scan_non_static!(b"5="), vec![(5, b"".to_vec(), 0)]);
+        assert_eq!(
+            scan_non_static!(b"foo=bar\x01=\x016=x=y"),
+            vec![(6, b"x=y".to_vec(), 10)]
+        );
+    }
+
+    #[test]
+    fn test_decode_extra_fields() {
+        let items: FixFieldList = scan_message_items(
+            b"8=FIX.4.4\x019=20\x0135=0\x015001=x\x0149=A\x015000=y\x0110=000\x01",
+        )
+        .collect();
+
+        assert_eq!(
+            decode_extra_fields(&items, &[&[35], &[49]], &[]),
+            vec![
+                FixExtraField::new(5001, b"x"),
+                FixExtraField::new(5000, b"y")
+            ]
+        );
+        assert_eq!(
+            decode_extra_fields(&items, &[&[35, 49, 5000, 5001]], &[]),
+            vec![]
+        );
+        assert_eq!(
+            decode_extra_fields(&items, &[&[35], &[49]], &[3..4, 6..7]),
+            vec![FixExtraField::new(5000, b"y")]
+        );
+    }
+
+    #[test]
+    fn test_scan_message_items_data_fields() {
+        let data = b"35=A\x0195=6\x0196=a\x01b=\x01c\x01108=30\x0193=3\x0189=\x01\x01\x01";
+
+        assert_eq!(
+            scan_non_static!(data, &[(95, 96), (93, 89)]),
+            vec![
+                (35, b"A".to_vec(), 0),
+                (95, b"6".to_vec(), 5),
+                (96, b"a\x01b=\x01c".to_vec(), 10),
+                (108, b"30".to_vec(), 20),
+                (93, b"3".to_vec(), 27),
+                (89, b"\x01\x01\x01".to_vec(), 32),
+            ]
+        );
+
+        // Without pairs, data is split on SOH
+        assert_eq!(scan_non_static!(data).len(), 6);
+    }
+
+    #[test]
+    fn test_scan_message_items_data_fields_bad_length() {
+        // Length does not match: fallback to SOH splitting
+        assert_eq!(
+            scan_non_static!(b"95=2\x0196=abc\x0110=0\x01", &[(95, 96)]),
+            vec![
+                (95, b"2".to_vec(), 0),
+                (96, b"abc".to_vec(), 5),
+                (10, b"0".to_vec(), 12),
+            ]
+        );
+        // Data field must directly follow its length
+        assert_eq!(
+            scan_non_static!(b"95=3\x0135=A\x0196=a\x01b\x01", &[(95, 96)]),
+            vec![
+                (95, b"3".to_vec(), 0),
+                (35, b"A".to_vec(), 5),
+                (96, b"a".to_vec(), 10),
+            ]
+        );
+        // Data at end of buffer
+        assert_eq!(
+            scan_non_static!(b"95=3\x0196=a\x01b", &[(95, 96)]),
+            vec![(95, b"3".to_vec(), 0), (96, b"a\x01b".to_vec(), 5)]
+        );
+    }
+
+    #[test]
+    fn test_scan_message_items_data_fields_length_overflow() {
+        // Length overflowing the buffer position: fallback to SOH splitting
+        assert_eq!(
+            scan_non_static!(
+                b"95=18446744073709551615\x0196=abc\x0110=0\x01",
+                &[(95, 96)]
+            ),
+            vec![
+                (95, b"18446744073709551615".to_vec(), 0),
+                (96, b"abc".to_vec(), 24),
+                (10, b"0".to_vec(), 31),
+            ]
         );
     }
 }
@@ -1,6 +1,8 @@
 use std::io::{self, Write};
 use std::num::Wrapping;
 
+use crate::{FixExtraField, FixGroupEntry};
+
 const NUM_BEGIN_STRING: &str = "8";
 const NUM_BODY_LENGTH: &str = "9";
 const NUM_CHECK_SUM: &str = "10";
@@ -17,6 +19,12 @@ pub struct FixEnvelopeBuilder {
     begin_string: String,
 }
 
+impl Default for FixEnvelopeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FixEnvelopeBuilder {
     pub fn new() -> Self {
         Self {
@@ -41,13 +49,8 @@ impl FixEnvelopeBuilder {
             data.len(),
         );
 
-        macro_rules! bytes_sum {
-            ($x:expr) => {
-                $x.iter().map(|x| Wrapping(*x)).sum::<Wrapping<u8>>()
-            };
-        }
-
-        let check_sum = bytes_sum!(header.as_bytes()) + bytes_sum!(data);
+        let check_sum =
+            Wrapping(compute_check_sum(header.as_bytes())) + Wrapping(compute_check_sum(data));
 
         writer.write_all(header.as_bytes())?;
         writer.write_all(data)?;
@@ -57,6 +60,45 @@ impl FixEnvelopeBuilder {
     }
 }
 
+/// Compute FIX check sum (sum of every bytes modulo 256) of given data.
+pub fn compute_check_sum(data: &[u8]) -> u8 {
+    data.iter().map(|x| Wrapping(*x)).sum::<Wrapping<u8>>().0
+}
+
+/// Write repeating group count field followed by each group entry.
+///
+/// Nothing is written when there is no entry.
+pub fn encode_group<W, T>(writer: &mut W, count_key: u32, entries: &[T]) -> io::Result<()>
+where
+    W: Write,
+    T: FixGroupEntry,
+{
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    write!(writer, "{}={}\x01", count_key, entries.len())?;
+    for entry in entries {
+        entry.encode_message(writer)?;
+    }
+
+    Ok(())
+}
+
+/// Write fields that are not part of the dictionary, as they were decoded.
+pub fn encode_extra_fields<W>(writer: &mut W, fields: &[FixExtraField]) -> io::Result<()>
+where
+    W: Write,
+{
+    for field in fields {
+        write!(writer, "{}=", field.tag)?;
+        writer.write_all(&field.value)?;
+        writer.write_all(b"\x01")?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,739 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+/// Convert field value from / to its FIX wire representation.
+///
+/// Generated fields delegate value encoding / decoding to this trait.
+pub trait FixFieldValue: Sized {
+    fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write;
+
+    /// Return `None` when value is not correctly formatted.
+    fn read_fix_value(value: &[u8]) -> Option<Self>;
+}
+
+/// Get FIX wire representation of a value.
+pub fn to_fix_bytes<T>(value: &T) -> Vec<u8>
+where
+    T: FixFieldValue,
+{
+    let mut data = Vec::new();
+    value
+        .write_fix_value(&mut data)
+        .expect("writing to Vec cannot fail");
+    data
+}
+
+/// Check number follows FIX rules: optional minus sign (when signed), ASCII digits,
+/// optional fractional part (when allowed). No plus sign, exponent, or surrounding spaces.
+fn is_fix_number(value: &[u8], signed: bool, fractional: bool) -> bool {
+    let digits = match value.strip_prefix(b"-") {
+        Some(digits) if signed => digits,
+        Some(_) => return false,
+        None => value,
+    };
+    let (integer, fraction) = match digits.iter().position(|x| *x == b'.') {
+        Some(position) if fractional => (&digits[..position], &digits[position + 1..]),
+        Some(_) => return false,
+        None => (digits, &digits[digits.len()..]),
+    };
+
+    !(integer.is_empty() && fraction.is_empty())
+        && integer.iter().all(u8::is_ascii_digit)
+        && fraction.iter().all(u8::is_ascii_digit)
+}
+
+/// INT, SEQNUM, LENGTH, NUMINGROUP, DAYOFMONTH: `[-]digits`, leading zeros allowed
+macro_rules! impl_fix_field_value_integer {
+    ($($t:ty => $signed:expr),*) => {
+        $(
+            impl FixFieldValue for $t {
+                fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+                where
+                    W: Write,
+                {
+                    write!(writer, "{}", self)
+                }
+
+                fn read_fix_value(value: &[u8]) -> Option<Self> {
+                    if !is_fix_number(value, $signed, false) {
+                        return None;
+                    }
+                    std::str::from_utf8(value).ok()?.parse().ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_fix_field_value_integer!(i32 => true, u8 => false, u32 => false, u64 => false, usize => false);
+
+/// FLOAT: `[-]digits[.digits]`, never written with an exponent
+impl FixFieldValue for f64 {
+    fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        if !self.is_finite() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} cannot be written as FIX float", self),
+            ));
+        }
+        // `Display` of f64 writes every digit, never an exponent
+        write!(writer, "{}", self)
+    }
+
+    fn read_fix_value(value: &[u8]) -> Option<Self> {
+        if !is_fix_number(value, true, true) {
+            return None;
+        }
+        std::str::from_utf8(value).ok()?.parse().ok()
+    }
+}
+
+/// BOOLEAN: `Y` or `N`
+impl FixFieldValue for bool {
+    fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(if *self { b"Y" } else { b"N" })
+    }
+
+    fn read_fix_value(value: &[u8]) -> Option<Self> {
+        match value {
+            b"Y" => Some(true),
+            b"N" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// CHAR: single character, which cannot be a space or a control character
+impl FixFieldValue for char {
+    fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write!(writer, "{}", self)
+    }
+
+    fn read_fix_value(value: &[u8]) -> Option<Self> {
+        let value: char = std::str::from_utf8(value).ok()?.parse().ok()?;
+        Some(value).filter(|x| !x.is_whitespace() && !x.is_control())
+    }
+}
+
+/// STRING and alike (CURRENCY, EXCHANGE, COUNTRY, ...)
+impl FixFieldValue for String {
+    fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(self.as_bytes())
+    }
+
+    fn read_fix_value(value: &[u8]) -> Option<Self> {
+        std::str::from_utf8(value).ok().map(String::from)
+    }
+}
+
+/// Value that can be part of a space separated list
+/// (MULTIPLEVALUESTRING, MULTIPLECHARVALUE, MULTIPLESTRINGVALUE).
+pub trait FixMultipleValue: FixFieldValue {}
+
+impl FixMultipleValue for char {}
+impl FixMultipleValue for String {}
+
+impl<T> FixFieldValue for Vec<T>
+where
+    T: FixMultipleValue,
+{
+    fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        for (idx, value) in self.iter().enumerate() {
+            if idx > 0 {
+                writer.write_all(b" ")?;
+            }
+            value.write_fix_value(writer)?;
+        }
+        Ok(())
+    }
+
+    fn read_fix_value(value: &[u8]) -> Option<Self> {
+        value
+            .split(|x| *x == b' ')
+            .map(|x| {
+                Some(x)
+                    .filter(|x| !x.is_empty())
+                    .and_then(T::read_fix_value)
+            })
+            .collect()
+    }
+}
+
+/// DATA: raw bytes, which may contain SOH
+impl FixFieldValue for Vec<u8> {
+    fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(self)
+    }
+
+    fn read_fix_value(value: &[u8]) -> Option<Self> {
+        Some(value.to_vec())
+    }
+}
+
+// =====================================
+// Decimal
+
+/// Value of PRICE, QTY, AMT, PRICEOFFSET and PERCENTAGE fields.
+///
+/// This is an exact decimal keeping wire text scale when `decimal` feature is enabled,
+/// and a plain `f64` otherwise.
+#[cfg(feature = "decimal")]
+pub type FixDecimal = rust_decimal::Decimal;
+
+/// Value of PRICE, QTY, AMT, PRICEOFFSET and PERCENTAGE fields.
+///
+/// This is an exact decimal keeping wire text scale when `decimal` feature is enabled,
+/// and a plain `f64` otherwise.
+#[cfg(not(feature = "decimal"))]
+pub type FixDecimal = f64;
+
+/// Conversion of `FixDecimal` from / to `f64`, whatever its representation is.
+pub trait FixDecimalExt: Sized {
+    /// Return `None` when value cannot be represented (NaN, infinite, out of range).
+    fn from_f64_value(value: f64) -> Option<Self>;
+
+    fn to_f64_value(&self) -> f64;
+}
+
+#[cfg(not(feature = "decimal"))]
+impl FixDecimalExt for f64 {
+    fn from_f64_value(value: f64) -> Option<Self> {
+        Some(value).filter(|x| x.is_finite())
+    }
+
+    fn to_f64_value(&self) -> f64 {
+        *self
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl FixDecimalExt for rust_decimal::Decimal {
+    fn from_f64_value(value: f64) -> Option<Self> {
+        use rust_decimal::prelude::FromPrimitive;
+        Self::from_f64(value)
+    }
+
+    fn to_f64_value(&self) -> f64 {
+        use rust_decimal::prelude::ToPrimitive;
+        self.to_f64().unwrap_or(f64::NAN)
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl FixFieldValue for rust_decimal::Decimal {
+    fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write!(writer, "{}", self)
+    }
+
+    fn read_fix_value(value: &[u8]) -> Option<Self> {
+        if !is_fix_number(value, true, true) {
+            return None;
+        }
+
+        Self::from_str_exact(std::str::from_utf8(value).ok()?).ok()
+    }
+}
+
+// =====================================
+// Date / time helpers
+
+fn parse_digits(value: &[u8]) -> Option<u32> {
+    if value.is_empty() || !value.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    std::str::from_utf8(value).ok()?.parse().ok()
+}
+
+/// Parse `YYYYMMDD`
+fn parse_date(value: &[u8]) -> Option<NaiveDate> {
+    if value.len() != 8 {
+        return None;
+    }
+
+    let year = parse_digits(&value[0..4])?;
+    let month = parse_digits(&value[4..6])?;
+    let day = parse_digits(&value[6..8])?;
+    NaiveDate::from_ymd_opt(year as i32, month, day)
+}
+
+/// Parse `HH:MM:SS[.sss[sss[sss]]]`
+fn parse_time(value: &[u8]) -> Option<NaiveTime> {
+    if value.len() < 8 || value[2] != b':' || value[5] != b':' {
+        return None;
+    }
+
+    let hour = parse_digits(&value[0..2])?;
+    let min = parse_digits(&value[3..5])?;
+    let mut sec = parse_digits(&value[6..8])?;
+
+    let mut nano = match &value[8..] {
+        [] => 0,
+        [b'.', fraction @ ..] if matches!(fraction.len(), 3 | 6 | 9) => {
+            parse_digits(fraction)? * 10u32.pow(9 - fraction.len() as u32)
+        }
+        _ => return None,
+    };
+
+    // Leap second is represented by chrono with nanoseconds overflow
+    if sec == 60 {
+        sec = 59;
+        nano += NANOS_PER_SEC;
+    }
+
+    NaiveTime::from_hms_nano_opt(hour, min, sec, nano)
+}
+
+fn write_date<W>(writer: &mut W, value: &NaiveDate) -> io::Result<()>
+where
+    W: Write,
+{
+    write!(
+        writer,
+        "{:04}{:02}{:02}",
+        value.year(),
+        value.month(),
+        value.day()
+    )
+}
+
+/// Write time using the shortest precision that does not lose information
+fn write_time<W>(writer: &mut W, value: &NaiveTime) -> io::Result<()>
+where
+    W: Write,
+{
+    let (sec, nano) = match value.nanosecond() {
+        x if x >= NANOS_PER_SEC => (60, x - NANOS_PER_SEC),
+        x => (value.second(), x),
+    };
+
+    write!(
+        writer,
+        "{:02}:{:02}:{:02}",
+        value.hour(),
+        value.minute(),
+        sec
+    )?;
+
+    if nano == 0 {
+        Ok(())
+    } else if nano % 1_000_000 == 0 {
+        write!(writer, ".{:03}", nano / 1_000_000)
+    } else if nano % 1_000 == 0 {
+        write!(writer, ".{:06}", nano / 1_000)
+    } else {
+        write!(writer, ".{:09}", nano)
+    }
+}
+
+// =====================================
+// Date / time implementations
+
+/// UTCTIMESTAMP: `YYYYMMDD-HH:MM:SS[.sss[sss[sss]]]`
+impl FixFieldValue for DateTime<Utc> {
+    fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let value = self.naive_utc();
+        write_date(writer, &value.date())?;
+        writer.write_all(b"-")?;
+        write_time(writer, &value.time())
+    }
+
+    fn read_fix_value(value: &[u8]) -> Option<Self> {
+        if value.len() < 9 || value[8] != b'-' {
+            return None;
+        }
+
+        let date = parse_date(&value[..8])?;
+        let time = parse_time(&value[9..])?;
+        Some(Utc.from_utc_datetime(&NaiveDateTime::new(date, time)))
+    }
+}
+
+/// UTCDATEONLY / LOCALMKTDATE: `YYYYMMDD`
+impl FixFieldValue for NaiveDate {
+    fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write_date(writer, self)
+    }
+
+    fn read_fix_value(value: &[u8]) -> Option<Self> {
+        parse_date(value)
+    }
+}
+
+/// UTCTIMEONLY: `HH:MM:SS[.sss[sss[sss]]]`
+impl FixFieldValue for NaiveTime {
+    fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write_time(writer, self)
+    }
+
+    fn read_fix_value(value: &[u8]) -> Option<Self> {
+        parse_time(value)
+    }
+}
+
+// =====================================
+// Month year
+
+/// Optional precision of a `MonthYear` value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonthYearPeriod {
+    /// Day of month (1-31)
+    Day(u32),
+    /// Week of month (1-5)
+    Week(u32),
+}
+
+/// MONTHYEAR: `YYYYMM`, `YYYYMMDD` or `YYYYMMwN`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonthYear {
+    pub year: u32,
+    pub month: u32,
+    pub period: Option<MonthYearPeriod>,
+}
+
+impl MonthYear {
+    pub fn new(year: u32, month: u32) -> Self {
+        Self {
+            year,
+            month,
+            period: None,
+        }
+    }
+
+    pub fn with_day(mut self, day: u32) -> Self {
+        self.period = Some(MonthYearPeriod::Day(day));
+        self
+    }
+
+    pub fn with_week(mut self, week: u32) -> Self {
+        self.period = Some(MonthYearPeriod::Week(week));
+        self
+    }
+}
+
+impl fmt::Display for MonthYear {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}{:02}", self.year, self.month)?;
+        match self.period {
+            Some(MonthYearPeriod::Day(day)) => write!(f, "{:02}", day),
+            Some(MonthYearPeriod::Week(week)) => write!(f, "w{}", week),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FixFieldValue for MonthYear {
+    fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write!(writer, "{}", self)
+    }
+
+    fn read_fix_value(value: &[u8]) -> Option<Self> {
+        if value.len() < 6 {
+            return None;
+        }
+
+        let year = parse_digits(&value[0..4])?;
+        let month = parse_digits(&value[4..6])?;
+        if !(1..=12).contains(&month) {
+            return None;
+        }
+
+        let period = match &value[6..] {
+            [] => None,
+            [b'w', week] => match parse_digits(&[*week])? {
+                week @ 1..=5 => Some(MonthYearPeriod::Week(week)),
+                _ => return None,
+            },
+            day if day.len() == 2 => {
+                let day = parse_digits(day)?;
+                NaiveDate::from_ymd_opt(year as i32, month, day)?;
+                Some(MonthYearPeriod::Day(day))
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            year,
+            month,
+            period,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode<T: FixFieldValue>(value: &T) -> String {
+        let mut data = Vec::new();
+        value.write_fix_value(&mut data).unwrap();
+        String::from_utf8(data).unwrap()
+    }
+
+    #[test]
+    fn test_utc_timestamp() {
+        let base = Utc.from_utc_datetime(
+            &NaiveDate::from_ymd_opt(2021, 4, 10)
+                .unwrap()
+                .and_hms_opt(19, 27, 37)
+                .unwrap(),
+        );
+
+        for (value, nano) in [
+            ("20210410-19:27:37", 0),
+            ("20210410-19:27:37.978", 978_000_000),
+            ("20210410-19:27:37.978062", 978_062_000),
+            ("20210410-19:27:37.978062231", 978_062_231),
+        ] {
+            let expected = base.with_nanosecond(nano).unwrap();
+            assert_eq!(
+                DateTime::<Utc>::read_fix_value(value.as_bytes()),
+                Some(expected)
+            );
+            assert_eq!(encode(&expected), value);
+        }
+
+        assert_eq!(
+            encode(&base.with_nanosecond(100_000_000).unwrap()),
+            "20210410-19:27:37.100"
+        );
+    }
+
+    #[test]
+    fn test_utc_timestamp_leap_second() {
+        let value = DateTime::<Utc>::read_fix_value(b"20161231-23:59:60.500").unwrap();
+        assert_eq!(value.second(), 59);
+        assert_eq!(value.nanosecond(), 1_500_000_000);
+        assert_eq!(encode(&value), "20161231-23:59:60.500");
+    }
+
+    #[test]
+    fn test_utc_timestamp_invalid() {
+        for value in [
+            "",
+            "1618082857.9780622",
+            "20210410",
+            "20210410-19:27",
+            "20210410 19:27:37",
+            "20210410-19:27:37.",
+            "20210410-19:27:37.97",
+            "20210410-19:27:37.9780",
+            "20210410-19:27:37.9780622",
+            "20210410-25:27:37",
+            "20210230-19:27:37",
+            "2021041a-19:27:37",
+            "20210410-+9:27:37",
+        ] {
+            assert_eq!(DateTime::<Utc>::read_fix_value(value.as_bytes()), None);
+        }
+    }
+
+    #[test]
+    fn test_date() {
+        let date = NaiveDate::from_ymd_opt(2021, 4, 1).unwrap();
+        assert_eq!(NaiveDate::read_fix_value(b"20210401"), Some(date));
+        assert_eq!(encode(&date), "20210401");
+
+        assert_eq!(NaiveDate::read_fix_value(b"2021041"), None);
+        assert_eq!(NaiveDate::read_fix_value(b"20211301"), None);
+        assert_eq!(NaiveDate::read_fix_value(b"2021-04-01"), None);
+    }
+
+    #[test]
+    fn test_time() {
+        let time = NaiveTime::from_hms_micro_opt(8, 5, 1, 42).unwrap();
+        assert_eq!(NaiveTime::read_fix_value(b"08:05:01.000042"), Some(time));
+        assert_eq!(encode(&time), "08:05:01.000042");
+
+        assert_eq!(
+            NaiveTime::read_fix_value(b"08:05:01"),
+            NaiveTime::from_hms_opt(8, 5, 1)
+        );
+        assert_eq!(NaiveTime::read_fix_value(b"8:05:01"), None);
+        assert_eq!(NaiveTime::read_fix_value(b"08:60:01"), None);
+    }
+
+    #[test]
+    fn test_month_year() {
+        for (value, expected) in [
+            ("202104", MonthYear::new(2021, 4)),
+            ("20210415", MonthYear::new(2021, 4).with_day(15)),
+            ("202104w2", MonthYear::new(2021, 4).with_week(2)),
+        ] {
+            assert_eq!(MonthYear::read_fix_value(value.as_bytes()), Some(expected));
+            assert_eq!(encode(&expected), value);
+        }
+
+        for value in [
+            "2021", "202113", "202100", "20210432", "202104w", "202104w6", "202104x1", "2021041",
+        ] {
+            assert_eq!(MonthYear::read_fix_value(value.as_bytes()), None);
+        }
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal() {
+        for value in [
+            "100.3",
+            "100.30",
+            "0.1",
+            "-0.00000001",
+            "42",
+            "79228162514264337593543950335",
+        ] {
+            let decimal = FixDecimal::read_fix_value(value.as_bytes()).unwrap();
+            assert_eq!(encode(&decimal), value);
+        }
+
+        for value in [
+            "",
+            "-",
+            "1e5",
+            "1_000",
+            "+1",
+            "1.2.3",
+            " 1",
+            "792281625142643375935439503350",
+        ] {
+            assert_eq!(FixDecimal::read_fix_value(value.as_bytes()), None);
+        }
+
+        let decimal = FixDecimal::read_fix_value(b"100.3").unwrap();
+        assert_eq!(decimal.to_f64_value(), 100.3);
+        assert_eq!(FixDecimal::from_f64_value(100.3), Some(decimal));
+        assert_eq!(FixDecimal::from_f64_value(f64::NAN), None);
+    }
+
+    #[cfg(not(feature = "decimal"))]
+    #[test]
+    fn test_decimal() {
+        assert_eq!(FixDecimal::read_fix_value(b"100.3"), Some(100.3));
+        assert_eq!(FixDecimal::from_f64_value(100.3), Some(100.3));
+        assert_eq!(FixDecimal::from_f64_value(f64::INFINITY), None);
+        assert_eq!(100.3.to_f64_value(), 100.3);
+    }
+
+    #[test]
+    fn test_multiple_value() {
+        assert_eq!(
+            Vec::<char>::read_fix_value(b"1 5 G"),
+            Some(vec!['1', '5', 'G'])
+        );
+        assert_eq!(encode(&vec!['1', '5', 'G']), "1 5 G");
+        assert_eq!(
+            Vec::<String>::read_fix_value(b"AB C"),
+            Some(vec!["AB".to_string(), "C".to_string()])
+        );
+
+        assert_eq!(Vec::<char>::read_fix_value(b"1 AB"), None);
+        assert_eq!(Vec::<char>::read_fix_value(b"1  5"), None);
+        assert_eq!(Vec::<String>::read_fix_value(b" A"), None);
+    }
+
+    #[test]
+    fn test_primitive() {
+        assert_eq!(i32::read_fix_value(b"-42"), Some(-42));
+        assert_eq!(i32::read_fix_value(b"foo"), None);
+        assert_eq!(String::read_fix_value(b"foo"), Some("foo".to_string()));
+        assert_eq!(encode(&12.5), "12.5");
+    }
+
+    #[test]
+    fn test_boolean() {
+        assert_eq!(bool::read_fix_value(b"Y"), Some(true));
+        assert_eq!(bool::read_fix_value(b"N"), Some(false));
+        assert_eq!(encode(&true), "Y");
+        assert_eq!(encode(&false), "N");
+
+        for value in ["", "true", "false", "y", "YES"] {
+            assert_eq!(bool::read_fix_value(value.as_bytes()), None);
+        }
+    }
+
+    #[test]
+    fn test_char() {
+        assert_eq!(char::read_fix_value(b"A"), Some('A'));
+        assert_eq!(encode(&'A'), "A");
+
+        for value in ["", "AB", " ", "\x01"] {
+            assert_eq!(char::read_fix_value(value.as_bytes()), None);
+        }
+    }
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(i32::read_fix_value(b"00042"), Some(42));
+        assert_eq!(u64::read_fix_value(b"18446744073709551615"), Some(u64::MAX));
+        assert_eq!(encode(&u64::MAX), "18446744073709551615");
+        assert_eq!(u32::read_fix_value(b"3"), Some(3));
+
+        for value in ["", "-", "+42", " 42", "42 ", "4.2", "1e3", "0x2A"] {
+            assert_eq!(i32::read_fix_value(value.as_bytes()), None);
+        }
+        assert_eq!(u64::read_fix_value(b"-1"), None);
+        assert_eq!(u64::read_fix_value(b"18446744073709551616"), None);
+    }
+
+    #[test]
+    fn test_float() {
+        for (value, expected) in [
+            ("12.5", 12.5),
+            ("-0.25", -0.25),
+            ("42", 42.0),
+            (".5", 0.5),
+            ("5.", 5.0),
+            ("007.50", 7.5),
+        ] {
+            assert_eq!(f64::read_fix_value(value.as_bytes()), Some(expected));
+        }
+        assert_eq!(encode(&1e21), "1000000000000000000000");
+        assert_eq!(encode(&1e-7), "0.0000001");
+
+        for value in [
+            "", ".", "-", "+1.5", "1e5", "1.5E-3", "inf", "NaN", "1.2.3", " 1",
+        ] {
+            assert_eq!(f64::read_fix_value(value.as_bytes()), None);
+        }
+        assert!(f64::NAN.write_fix_value(&mut Vec::new()).is_err());
+        assert!(f64::INFINITY.write_fix_value(&mut Vec::new()).is_err());
+    }
+}
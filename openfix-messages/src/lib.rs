@@ -1,5 +1,8 @@
+#[cfg(feature = "tokio")]
+pub mod codec;
 pub mod dec_helpers;
 pub mod enc_helpers;
+pub mod field_types;
 
 #[cfg(feature = "fixt11")]
 pub mod fixt11 {
@@ -83,21 +86,39 @@ pub mod test_spec_sig {
 
 pub mod prelude {
     pub use super::{
-        AsFixMessage, AsFixMessageField, FixFieldItems, FixParseError, FromFixMessage,
-        FromFixMessageField, MessageDest,
+        AsFixMessage, AsFixMessageField, FixBuildError, FixComponent, FixExtraField,
+        FixFieldContext, FixFieldItem, FixFieldItems, FixGroupEntry, FixParseError,
+        FixSessionContext, FromFixMessage, FromFixMessageField, MessageDest,
     };
+
+    pub use crate::dec_helpers::{decode_extra_fields, decode_group, group_range};
+    pub use crate::enc_helpers::{encode_extra_fields, encode_group};
+    pub use crate::field_types::{
+        to_fix_bytes, FixDecimal, FixDecimalExt, FixFieldValue, FixMultipleValue, MonthYear,
+    };
+    pub use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 }
 
+use chrono::{DateTime, Utc};
+use std::fmt;
 use std::io::{self, Write};
+use std::ops::Range;
 use std::str::Utf8Error;
 use thiserror::Error;
 
-pub use crate::dec_helpers::FixFieldItems;
+pub use chrono;
+#[cfg(feature = "macros")]
+pub use openfix_macros::dictionary;
+
+pub use crate::dec_helpers::{FixExtraField, FixFieldItem, FixFieldItems, FixFieldList};
 
 pub trait AsFixMessageField {
     /// Fix key representation
     const FIX_KEY: u32;
 
+    /// Field name, as defined in dictionary
+    const FIX_NAME: &'static str;
+
     /// FIX value representation
     fn encode_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
     where
@@ -113,6 +134,65 @@ pub trait AsFixMessageField {
         write!(writer, "\x01")?;
         Ok(())
     }
+
+    /// Build error context for this field
+    fn error_context(value: Option<&[u8]>) -> FixFieldContext
+    where
+        Self: Sized,
+    {
+        let context = FixFieldContext::new(Self::FIX_KEY, Self::FIX_NAME);
+        match value {
+            Some(value) => context.with_value(value),
+            None => context,
+        }
+    }
+}
+
+/// Describe which field has failed to decode, and where.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixFieldContext {
+    /// Field ID
+    pub tag: u32,
+    /// Field name, as defined in dictionary
+    pub name: &'static str,
+    /// Name of the message / component / group containing the field
+    pub parent: Option<&'static str>,
+    /// Raw field value, when there is one
+    pub value: Option<String>,
+}
+
+impl FixFieldContext {
+    pub fn new(tag: u32, name: &'static str) -> Self {
+        Self {
+            tag,
+            name,
+            parent: None,
+            value: None,
+        }
+    }
+
+    pub fn with_parent(mut self, parent: &'static str) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    pub fn with_value(mut self, value: &[u8]) -> Self {
+        self.value = Some(String::from_utf8_lossy(value).into_owned());
+        self
+    }
+}
+
+impl fmt::Display for FixFieldContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.tag)?;
+        if let Some(parent) = self.parent {
+            write!(f, " in {}", parent)?;
+        }
+        if let Some(value) = &self.value {
+            write!(f, " with value {:?}", value)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq, Error)]
@@ -125,6 +205,106 @@ pub enum FixParseError {
 
     #[error("no data for this field ID")]
     NoData,
+
+    #[error("required field {0} is missing")]
+    MissingRequiredField(FixFieldContext),
+
+    #[error("field {0} has no value")]
+    MissingValue(FixFieldContext),
+
+    #[error("field {0} is not a valid enum value")]
+    InvalidEnumValue(FixFieldContext),
+
+    #[error("field {0} has an incorrect data format")]
+    InvalidFormat(FixFieldContext),
+
+    #[error("repeating group {context} declares {declared} entries but has {actual}")]
+    InvalidGroupCount {
+        context: FixFieldContext,
+        declared: usize,
+        actual: usize,
+    },
+
+    #[error("message type {0} is not supported")]
+    InvalidMsgType(FixFieldContext),
+
+    #[error("BeginString (8) must be the first field")]
+    MissingBeginString,
+
+    #[error("unexpected BeginString (8) value")]
+    InvalidBeginString,
+
+    #[error("BodyLength (9) must be the second field")]
+    MissingBodyLength,
+
+    #[error("MsgType (35) must be the third field")]
+    MissingMsgType,
+
+    #[error("CheckSum (10) must be the last field")]
+    MissingCheckSum,
+
+    #[error("BodyLength mismatch: declared {declared}, actual {actual}")]
+    InvalidBodyLength { declared: usize, actual: usize },
+
+    #[error("CheckSum mismatch: declared {declared:03}, computed {computed:03}")]
+    InvalidCheckSum { declared: u16, computed: u8 },
+
+    #[error("invalid frame: {skipped} bytes skipped")]
+    InvalidFrame { skipped: usize },
+}
+
+impl FixParseError {
+    /// Get field context of the error, if error is related to a field
+    pub fn context(&self) -> Option<&FixFieldContext> {
+        match self {
+            Self::MissingRequiredField(context)
+            | Self::MissingValue(context)
+            | Self::InvalidEnumValue(context)
+            | Self::InvalidFormat(context)
+            | Self::InvalidGroupCount { context, .. }
+            | Self::InvalidMsgType(context) => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Set name of the message / component / group containing the field.
+    ///
+    /// Name is only set once, so the innermost one is kept.
+    pub fn with_parent(mut self, parent: &'static str) -> Self {
+        match &mut self {
+            Self::MissingRequiredField(context)
+            | Self::MissingValue(context)
+            | Self::InvalidEnumValue(context)
+            | Self::InvalidFormat(context)
+            | Self::InvalidGroupCount { context, .. }
+            | Self::InvalidMsgType(context) => {
+                context.parent.get_or_insert(parent);
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Get SessionRejectReason (373) code matching this error.
+    ///
+    /// Return `None` when error does not match any reject reason (which is the
+    /// case of garbled messages, that must be ignored instead of rejected).
+    pub fn session_reject_reason(&self) -> Option<u32> {
+        match self {
+            Self::MissingRequiredField(_) => Some(1),
+            Self::MissingValue(_) => Some(4),
+            Self::InvalidEnumValue(_) => Some(5),
+            Self::InvalidFormat(_) | Self::InvalidString(_) => Some(6),
+            Self::InvalidMsgType(_) => Some(11),
+            Self::InvalidGroupCount { .. } => Some(16),
+            _ => None,
+        }
+    }
+
+    /// Get RefTagID (371) of the field causing the error
+    pub fn ref_tag_id(&self) -> Option<u32> {
+        self.context().map(|x| x.tag)
+    }
 }
 
 pub trait FromFixMessageField: AsFixMessageField {
@@ -133,6 +313,17 @@ pub trait FromFixMessageField: AsFixMessageField {
     where
         Self: Sized;
 
+    /// Decode field from its raw value, rejecting empty values
+    fn from_fix_item(value: &[u8]) -> Result<Self, FixParseError>
+    where
+        Self: Sized,
+    {
+        if value.is_empty() {
+            return Err(FixParseError::MissingValue(Self::error_context(None)));
+        }
+        Self::from_fix_value(value)
+    }
+
     /// Decode field from map of (key ID => value) data
     fn decode_message(items: &FixFieldItems) -> Result<Self, FixParseError>
     where
@@ -141,9 +332,30 @@ pub trait FromFixMessageField: AsFixMessageField {
         let key_id = Self::FIX_KEY;
         let data = items
             .get(&key_id)
-            .ok_or_else(|| FixParseError::InvalidData)?;
+            .ok_or_else(|| FixParseError::MissingRequiredField(Self::error_context(None)))?;
+
+        Self::from_fix_item(data)
+    }
 
-        Self::from_fix_value(&data)
+    /// Decode field from ordered list of items, using the first matching item
+    fn decode_items(items: &[FixFieldItem]) -> Result<Self, FixParseError>
+    where
+        Self: Sized,
+    {
+        Self::decode_optional_items(items)?
+            .ok_or_else(|| FixParseError::MissingRequiredField(Self::error_context(None)))
+    }
+
+    /// Same as `decode_items`, but return `None` when field is not present
+    fn decode_optional_items(items: &[FixFieldItem]) -> Result<Option<Self>, FixParseError>
+    where
+        Self: Sized,
+    {
+        let key_id = Self::FIX_KEY;
+        match items.iter().find(|x| x.tag == key_id) {
+            Some(item) => Self::from_fix_item(item.value).map(Some),
+            None => Ok(None),
+        }
     }
 }
 
@@ -154,22 +366,105 @@ pub trait AsFixMessage {
 }
 
 pub trait FromFixMessage {
+    /// Decode message from map of (key ID => value) data
+    ///
+    /// Map has no field order, so repeating groups cannot be decoded from it.
     fn decode_message(items: &FixFieldItems) -> Result<Self, FixParseError>
+    where
+        Self: Sized,
+    {
+        let items: FixFieldList = items
+            .iter()
+            .map(|(tag, value)| FixFieldItem {
+                tag: *tag,
+                value,
+                offset: 0,
+            })
+            .collect();
+
+        Self::decode_items(&items)
+    }
+
+    /// Decode message from ordered list of items
+    fn decode_items(items: &[FixFieldItem]) -> Result<Self, FixParseError>
     where
         Self: Sized;
 }
 
+/// Set of fields encoded / decoded together (component, group entry)
+pub trait FixComponent: AsFixMessage + FromFixMessage {
+    /// Every field ID the component may contain (nested components and groups included)
+    const FIX_TAGS: &'static [u32];
+
+    /// Get ranges of items covered by repeating groups of the component (nested
+    /// groups excluded, since they are covered by their parent group)
+    fn group_ranges(items: &[FixFieldItem]) -> Vec<Range<usize>>;
+
+    /// Decode component only if at least one of its fields is present
+    fn decode_optional_items(items: &[FixFieldItem]) -> Result<Option<Self>, FixParseError>
+    where
+        Self: Sized,
+    {
+        if items.iter().any(|x| Self::FIX_TAGS.contains(&x.tag)) {
+            Self::decode_items(items).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Single entry of a FIX repeating group
+pub trait FixGroupEntry: FixComponent {
+    /// First field of an entry, marking start of each entry
+    const DELIMITER: u32;
+
+    /// Every field ID of the dictionary, sorted.
+    ///
+    /// An entry stops at the first one which is not part of the group, while
+    /// fields missing from dictionary are kept in the entry.
+    const DICTIONARY_TAGS: &'static [u32];
+}
+
 #[derive(Debug, PartialEq)]
 pub enum MessageDest {
     Admin,
     App,
 }
 
+#[derive(Debug, PartialEq, Error)]
+pub enum FixBuildError {
+    #[error("missing required fields: {}", .0.join(", "))]
+    MissingRequiredFields(Vec<&'static str>),
+}
+
+impl FixBuildError {
+    /// Name of every required field that has not been set
+    pub fn missing_fields(&self) -> &[&'static str] {
+        match self {
+            Self::MissingRequiredFields(fields) => fields,
+        }
+    }
+}
+
+/// Session state used by message builders to fill standard header fields.
+pub trait FixSessionContext {
+    fn sender_comp_id(&self) -> &str;
+
+    fn target_comp_id(&self) -> &str;
+
+    /// Sequence number of the message being built, called once per message
+    fn next_msg_seq_num(&mut self) -> u64;
+
+    fn sending_time(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::dec_helpers::split_message_items;
+    use crate::dec_helpers::{scan_message_items, split_message_items};
 
     #[derive(Debug, PartialEq)]
     struct TestStruct {
@@ -178,6 +473,7 @@ mod tests {
 
     impl AsFixMessageField for TestStruct {
         const FIX_KEY: u32 = 42;
+        const FIX_NAME: &'static str = "TestStruct";
 
         fn encode_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
         where
@@ -215,15 +511,24 @@ mod tests {
     fn test_struct_decode() {
         assert_eq!(
             TestStruct::decode_message(&split_message_items(b"foo")),
-            Err(FixParseError::InvalidData)
+            Err(FixParseError::MissingRequiredField(FixFieldContext::new(
+                42,
+                "TestStruct"
+            )))
         );
         assert_eq!(
             TestStruct::decode_message(&split_message_items(b"foo=bar")),
-            Err(FixParseError::InvalidData)
+            Err(FixParseError::MissingRequiredField(FixFieldContext::new(
+                42,
+                "TestStruct"
+            )))
         );
         assert_eq!(
             TestStruct::decode_message(&split_message_items(b"12=bar")),
-            Err(FixParseError::InvalidData)
+            Err(FixParseError::MissingRequiredField(FixFieldContext::new(
+                42,
+                "TestStruct"
+            )))
         );
         assert_eq!(
             TestStruct::decode_message(&split_message_items(b"42=foobar")),
@@ -233,6 +538,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_struct_decode_items() {
+        macro_rules! decode_items {
+            ($x:expr) => {
+                TestStruct::decode_items(&scan_message_items($x).collect::<FixFieldList>())
+            };
+        }
+
+        assert_eq!(
+            decode_items!(b"12=bar"),
+            Err(FixParseError::MissingRequiredField(FixFieldContext::new(
+                42,
+                "TestStruct"
+            )))
+        );
+        assert_eq!(
+            decode_items!(b"42="),
+            Err(FixParseError::MissingValue(FixFieldContext::new(
+                42,
+                "TestStruct"
+            )))
+        );
+        assert_eq!(
+            decode_items!(b"42=foo\x0142=bar"),
+            Ok(TestStruct {
+                value: "foo".into(),
+            })
+        );
+    }
+
     #[derive(Debug, PartialEq)]
     enum TestEnum {
         Opt1,
@@ -241,6 +576,7 @@ mod tests {
 
     impl AsFixMessageField for TestEnum {
         const FIX_KEY: u32 = 29;
+        const FIX_NAME: &'static str = "TestEnum";
 
         fn encode_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
         where
@@ -262,7 +598,9 @@ mod tests {
             match value {
                 b"opt1" => Ok(Self::Opt1),
                 b"opt2" => Ok(Self::Opt2),
-                _ => Err(FixParseError::InvalidData),
+                _ => Err(FixParseError::InvalidEnumValue(Self::error_context(Some(
+                    value,
+                )))),
             }
         }
     }
@@ -279,15 +617,21 @@ mod tests {
     fn test_enum_decode() {
         assert_eq!(
             TestEnum::decode_message(&split_message_items(b"foo")),
-            Err(FixParseError::InvalidData)
+            Err(FixParseError::MissingRequiredField(FixFieldContext::new(
+                29, "TestEnum"
+            )))
         );
         assert_eq!(
             TestEnum::decode_message(&split_message_items(b"foo=bar")),
-            Err(FixParseError::InvalidData)
+            Err(FixParseError::MissingRequiredField(FixFieldContext::new(
+                29, "TestEnum"
+            )))
         );
         assert_eq!(
             TestEnum::decode_message(&split_message_items(b"12=bar")),
-            Err(FixParseError::InvalidData)
+            Err(FixParseError::MissingRequiredField(FixFieldContext::new(
+                29, "TestEnum"
+            )))
         );
         assert_eq!(
             TestEnum::decode_message(&split_message_items(b"29=opt1")),
@@ -297,5 +641,41 @@ mod tests {
             TestEnum::decode_message(&split_message_items(b"29=opt2")),
             Ok(TestEnum::Opt2)
         );
+        assert_eq!(
+            TestEnum::decode_message(&split_message_items(b"29=opt3")),
+            Err(FixParseError::InvalidEnumValue(
+                FixFieldContext::new(29, "TestEnum").with_value(b"opt3")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_error_context() {
+        let error = FixParseError::InvalidEnumValue(
+            FixFieldContext::new(29, "TestEnum").with_value(b"opt3"),
+        )
+        .with_parent("TestComponent")
+        .with_parent("TestMessage");
+
+        assert_eq!(
+            error.to_string(),
+            "field TestEnum (29) in TestComponent with value \"opt3\" is not a valid enum value"
+        );
+        assert_eq!(error.session_reject_reason(), Some(5));
+        assert_eq!(error.ref_tag_id(), Some(29));
+
+        let error = FixParseError::MissingRequiredField(FixFieldContext::new(42, "TestStruct"));
+        assert_eq!(
+            error.to_string(),
+            "required field TestStruct (42) is missing"
+        );
+        assert_eq!(error.session_reject_reason(), Some(1));
+
+        let error = FixParseError::InvalidCheckSum {
+            declared: 12,
+            computed: 42,
+        };
+        assert_eq!(error.session_reject_reason(), None);
+        assert_eq!(error.ref_tag_id(), None);
     }
 }
@@ -0,0 +1,151 @@
+use openfix_messages::chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use openfix_messages::enc_helpers::FixEnvelopeBuilder;
+use openfix_messages::field_types::{FixDecimal, FixDecimalExt};
+use openfix_messages::fix44::fields::*;
+use openfix_messages::fix44::messages::*;
+use openfix_messages::{AsFixMessage, FixBuildError, FixSessionContext};
+
+struct TestSession {
+    next_seq_num: u64,
+}
+
+impl FixSessionContext for TestSession {
+    fn sender_comp_id(&self) -> &str {
+        "CLIENT"
+    }
+
+    fn target_comp_id(&self) -> &str {
+        "BROKER"
+    }
+
+    fn next_msg_seq_num(&mut self) -> u64 {
+        self.next_seq_num += 1;
+        self.next_seq_num - 1
+    }
+
+    fn sending_time(&self) -> DateTime<Utc> {
+        Utc.from_utc_datetime(
+            &NaiveDate::from_ymd_opt(2021, 4, 10)
+                .unwrap()
+                .and_hms_milli_opt(19, 27, 37, 978)
+                .unwrap(),
+        )
+    }
+}
+
+fn decimal(value: f64) -> FixDecimal {
+    FixDecimal::from_f64_value(value).unwrap()
+}
+
+fn build_order(session: &mut TestSession) -> MessageNewOrderSingle {
+    MessageNewOrderSingle::builder()
+        .with_session(session)
+        .cl_ord_id(ClOrdID::new("ORD1".into()))
+        .instrument(
+            Instrument::builder()
+                .symbol(Symbol::new("AAPL".into()))
+                .build()
+                .unwrap(),
+        )
+        .side(Side::Buy)
+        .transact_time(TransactTime::new(session.sending_time()))
+        .order_qty_data(
+            OrderQtyData::builder()
+                .order_qty(OrderQty::new(decimal(100.0)))
+                .build()
+                .unwrap(),
+        )
+        .ord_type(OrdType::Limit)
+        .price(Price::new(decimal(12.5)))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_build_with_session() -> anyhow::Result<()> {
+    let mut session = TestSession { next_seq_num: 42 };
+    let message = build_order(&mut session);
+
+    assert_eq!(message.header.msg_type, MsgType::OrderSingle);
+    assert_eq!(
+        message.header.sender_comp_id,
+        SenderCompID::new("CLIENT".into())
+    );
+    assert_eq!(
+        message.header.target_comp_id,
+        TargetCompID::new("BROKER".into())
+    );
+    assert_eq!(message.header.msg_seq_num, MsgSeqNum::new(42));
+    assert_eq!(message.header.on_behalf_of_comp_id, None);
+    assert_eq!(
+        build_order(&mut session).header.msg_seq_num,
+        MsgSeqNum::new(43)
+    );
+
+    let mut data = Vec::new();
+    message.encode_message(&mut data)?;
+    let mut frame = Vec::new();
+    FixEnvelopeBuilder::new().build_message(&mut frame, &data)?;
+
+    assert_eq!(
+        frame,
+        b"8=FIX.4.4\x019=122\x0135=D\x0149=CLIENT\x0156=BROKER\x0134=42\x0152=20210410-19:27:37.978\x01\
+11=ORD1\x0155=AAPL\x0154=1\x0160=20210410-19:27:37.978\x0138=100\x0140=2\x0144=12.5\x0110=141\x01"
+            .to_vec()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_build_update_header() {
+    let mut session = TestSession { next_seq_num: 1 };
+    let message = MessageHeartbeat::builder()
+        .with_session(&mut session)
+        .with_header(|header| header.on_behalf_of_comp_id(OnBehalfOfCompID::new("DESK".into())))
+        .build()
+        .unwrap();
+
+    assert_eq!(message.header.msg_type, MsgType::Heartbeat);
+    assert_eq!(
+        message.header.on_behalf_of_comp_id,
+        Some(OnBehalfOfCompID::new("DESK".into()))
+    );
+    assert_eq!(message.test_req_id, None);
+
+    let copy = MessageHeartbeat::builder()
+        .header(message.header)
+        .build()
+        .unwrap();
+    assert_eq!(copy.header.msg_seq_num, MsgSeqNum::new(1));
+}
+
+#[test]
+fn test_build_missing_fields() {
+    assert_eq!(
+        MessageNewOrderSingle::builder()
+            .cl_ord_id(ClOrdID::new("ORD1".into()))
+            .ord_type(OrdType::Market)
+            .build(),
+        Err(FixBuildError::MissingRequiredFields(vec![
+            "MsgType",
+            "SenderCompID",
+            "TargetCompID",
+            "MsgSeqNum",
+            "SendingTime",
+            "Instrument",
+            "Side",
+            "TransactTime",
+            "OrderQtyData",
+        ]))
+    );
+
+    let mut session = TestSession { next_seq_num: 1 };
+    let err = MessageNewOrderSingle::builder()
+        .with_session(&mut session)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "missing required fields: ClOrdID, Instrument, Side, TransactTime, OrderQtyData, OrdType"
+    );
+}
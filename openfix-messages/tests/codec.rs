@@ -0,0 +1,142 @@
+use futures::{SinkExt, StreamExt};
+use openfix_messages::chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use openfix_messages::codec::{FixCodec, FixFrameCodec};
+use openfix_messages::fix44::fields::*;
+use openfix_messages::fix44::messages::*;
+use openfix_messages::{FixParseError, FixSessionContext};
+use tokio::io::AsyncWriteExt;
+use tokio_util::codec::{Framed, FramedRead};
+
+struct TestSession {
+    next_seq_num: u64,
+}
+
+impl FixSessionContext for TestSession {
+    fn sender_comp_id(&self) -> &str {
+        "CLIENT"
+    }
+
+    fn target_comp_id(&self) -> &str {
+        "BROKER"
+    }
+
+    fn next_msg_seq_num(&mut self) -> u64 {
+        self.next_seq_num += 1;
+        self.next_seq_num - 1
+    }
+
+    fn sending_time(&self) -> DateTime<Utc> {
+        Utc.from_utc_datetime(
+            &NaiveDate::from_ymd_opt(2021, 4, 10)
+                .unwrap()
+                .and_hms_milli_opt(19, 27, 37, 978)
+                .unwrap(),
+        )
+    }
+}
+
+fn build_heartbeat(session: &mut TestSession, test_req_id: &str) -> MessageHeartbeat {
+    MessageHeartbeat::builder()
+        .with_session(session)
+        .test_req_id(TestReqID::new(test_req_id.into()))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_codec_round_trip() {
+    let (client, broker) = tokio::io::duplex(4096);
+    let codec = || {
+        FixCodec::<Message>::new()
+            .begin_string("FIX.4.4")
+            .with_data_fields(DATA_FIELDS)
+    };
+    let mut client = Framed::new(client, codec());
+    let mut broker = Framed::new(broker, codec());
+
+    let mut session = TestSession { next_seq_num: 1 };
+    client
+        .send(build_heartbeat(&mut session, "TEST1"))
+        .await
+        .unwrap();
+    client
+        .send(build_heartbeat(&mut session, "TEST2"))
+        .await
+        .unwrap();
+    drop(client);
+
+    for (msg_seq_num, test_req_id) in [(1, "TEST1"), (2, "TEST2")] {
+        match broker.next().await {
+            Some(Ok(Ok(Message::Heartbeat(message)))) => {
+                assert_eq!(message.header.msg_seq_num, MsgSeqNum::new(msg_seq_num));
+                assert_eq!(
+                    message.test_req_id,
+                    Some(TestReqID::new(test_req_id.into()))
+                );
+            }
+            other => panic!("Unexpected item: {:?}", other),
+        }
+    }
+    assert!(broker.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_codec_invalid_frames() {
+    let (mut client, broker) = tokio::io::duplex(4096);
+    let mut broker = FramedRead::new(broker, FixFrameCodec::new());
+
+    client
+        .write_all(b"8=FIX.4.4\x019=5\x0135=0\x0110=000\x01")
+        .await
+        .unwrap();
+    client
+        .write_all(b"8=FIX.4.4\x019=5\x0135=0\x0110=163\x01")
+        .await
+        .unwrap();
+    client.write_all(b"8=FIX.4.4\x019=5").await.unwrap();
+    drop(client);
+
+    assert_eq!(
+        broker.next().await.unwrap().unwrap(),
+        Err(FixParseError::InvalidCheckSum {
+            declared: 0,
+            computed: 163
+        })
+    );
+    assert_eq!(
+        broker.next().await.unwrap().unwrap(),
+        Ok(b"8=FIX.4.4\x019=5\x0135=0\x0110=163\x01"[..].into())
+    );
+    assert_eq!(
+        broker.next().await.unwrap().unwrap(),
+        Err(FixParseError::InvalidFrame { skipped: 13 })
+    );
+    assert!(broker.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_codec_max_frame_length() {
+    let (mut client, broker) = tokio::io::duplex(4096);
+    let codec = FixCodec::<Message>::new().max_frame_length(64);
+    let mut broker = FramedRead::new(broker, codec);
+
+    client
+        .write_all(b"8=FIX.4.4\x019=18446744073709551600\x0135=0\x01")
+        .await
+        .unwrap();
+    client
+        .write_all(b"8=FIX.4.4\x019=1000\x0135=0\x01")
+        .await
+        .unwrap();
+    client.write_all(&[b'A'; 100]).await.unwrap();
+    drop(client);
+
+    // Overflowing body length is garbage, but frame too large ends the stream
+    assert_eq!(
+        broker.next().await.unwrap().unwrap(),
+        Err(FixParseError::InvalidFrame { skipped: 38 })
+    );
+    let err = broker.next().await.unwrap().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(broker.next().await.is_none());
+}
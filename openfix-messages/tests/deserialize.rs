@@ -1,7 +1,10 @@
-use openfix_messages::dec_helpers::split_message_items;
+use openfix_messages::chrono::{NaiveDate, TimeZone, Utc};
+use openfix_messages::dec_helpers::{
+    scan_message_items, split_message_items, FixEnvelopeValidator,
+};
 use openfix_messages::test_spec_sig::fields::*;
 use openfix_messages::test_spec_sig::messages::*;
-use openfix_messages::FromFixMessage;
+use openfix_messages::{FixFieldContext, FixFieldList, FixParseError, FromFixMessage, MessageDest};
 
 fn build_header() -> MessageHeader {
     MessageHeader {
@@ -9,7 +12,14 @@ fn build_header() -> MessageHeader {
         sender_comp_id: SenderCompID::new("BROKER".into()),
         target_comp_id: TargetCompID::new("MARKET".into()),
         msg_seq_num: MsgSeqNum::new(23593),
-        sending_time: SendingTime::new(1618082857.9780622),
+        sending_time: SendingTime::new(
+            Utc.from_utc_datetime(
+                &NaiveDate::from_ymd_opt(2021, 4, 10)
+                    .unwrap()
+                    .and_hms_milli_opt(19, 27, 37, 978)
+                    .unwrap(),
+            ),
+        ),
         appl_ver_id: Some(ApplVerID::Fix42),
     }
 }
@@ -17,8 +27,8 @@ fn build_header() -> MessageHeader {
 fn build_trailer() -> MessageTrailer {
     let author = "arthurlm".to_string();
     MessageTrailer {
-        signature_length: Some(SignatureLength::new(author.len())),
-        signature: Some(Signature::new(author)),
+        signature_length: Some(SignatureLength::new(author.len() as u64)),
+        signature: Some(Signature::new(author.into_bytes())),
     }
 }
 
@@ -27,13 +37,58 @@ fn build_hb() -> MessageHeartbeat {
         header: build_header(),
         trailer: build_trailer(),
         test_req_id: None,
+        extra_fields: vec![],
     }
 }
 
 #[test]
 fn test_deserialize() {
-    compare_data(b"8=FIX.4.4\x019=80\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=1618082857.9780622\x011128=4\x0193=8\x0189=arthurlm\x0110=239\x01");
-    compare_data(b"8=FIX.4.4\x019=80\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=1618082857.9780622\x011128=4\x0193=8\x0189=arthurlm\x0110=239\x01\x01\x01\x01");
+    compare_data(b"8=FIX.4.4\x019=83\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=20210410-19:27:37.978\x011128=4\x0193=8\x0189=arthurlm\x0110=130\x01");
+    compare_data(b"8=FIX.4.4\x019=83\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=20210410-19:27:37.978\x011128=4\x0193=8\x0189=arthurlm\x0110=130\x01\x01\x01\x01");
+}
+
+#[test]
+fn test_deserialize_validated() {
+    let validator = FixEnvelopeValidator::new().begin_string("FIX.4.4");
+    let body = validator.validate_message(b"8=FIX.4.4\x019=83\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=20210410-19:27:37.978\x011128=4\x0193=8\x0189=arthurlm\x0110=130\x01").unwrap();
+    compare_data(body);
+}
+
+#[test]
+fn test_deserialize_any() {
+    let items: FixFieldList = scan_message_items(b"8=FIX.4.4\x019=83\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=20210410-19:27:37.978\x011128=4\x0193=8\x0189=arthurlm\x0110=130\x01").collect();
+    let message = Message::decode_any(&items).unwrap();
+
+    assert_eq!(message.msg_type(), "0");
+    assert_eq!(message.dest(), MessageDest::Admin);
+    assert_eq!(message, Message::Heartbeat(build_hb()));
+
+    let items: FixFieldList = scan_message_items(
+        b"35=1\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=20210410-19:27:37.978\x01112=ping\x01",
+    )
+    .collect();
+    match Message::decode_any(&items).unwrap() {
+        Message::TestRequest(message) => {
+            assert_eq!(message.test_req_id, TestReqID::new("ping".into()))
+        }
+        message => panic!("unexpected message: {:?}", message),
+    }
+
+    let items: FixFieldList = scan_message_items(b"35=Z\x0149=BROKER\x01").collect();
+    assert_eq!(
+        Message::decode_any(&items),
+        Err(FixParseError::InvalidMsgType(
+            FixFieldContext::new(35, "MsgType").with_value(b"Z")
+        ))
+    );
+
+    let items: FixFieldList = scan_message_items(b"49=BROKER\x01").collect();
+    assert_eq!(
+        Message::decode_any(&items),
+        Err(FixParseError::MissingRequiredField(FixFieldContext::new(
+            35, "MsgType"
+        )))
+    );
 }
 
 fn compare_data(data: &[u8]) {
@@ -41,3 +96,22 @@ fn compare_data(data: &[u8]) {
     let message = MessageHeartbeat::decode_message(&split_message_items(data)).unwrap();
     assert_eq!(message, expected);
 }
+
+#[test]
+fn test_deserialize_binary_signature() {
+    let data = b"35=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=20210410-19:27:37.978\x011128=4\x0193=5\x0189=a\x01b\x01c\x01";
+    let items: FixFieldList = scan_message_items(data)
+        .with_data_fields(DATA_FIELDS)
+        .collect();
+    let message = MessageHeartbeat::decode_items(&items).unwrap();
+
+    assert_eq!(
+        message.trailer.signature,
+        Some(Signature::new(b"a\x01b\x01c".to_vec()))
+    );
+    assert_eq!(
+        message.trailer.signature_length,
+        Some(SignatureLength::new(5))
+    );
+    assert!(message.extra_fields.is_empty());
+}
@@ -0,0 +1,56 @@
+use openfix_messages::dec_helpers::scan_message_items;
+use openfix_messages::fix44::fields::*;
+use openfix_messages::fix44::messages::*;
+use openfix_messages::{AsFixMessage, FixExtraField, FixFieldList, FromFixMessage};
+
+const EXECUTION_REPORT: &[u8] =
+    b"35=8\x0149=BROKER\x0156=CLIENT\x0134=2\x0152=20210410-19:27:37.978\x015001=VENUE\x01\
+37=ORD1\x0117=EXEC1\x01150=0\x019000=abc\x0139=0\x0155=AAPL\x0154=1\x01151=100\x0114=0\x016=0\x01\
+5000=x=y\x01";
+
+fn decode_execution_report(data: &[u8]) -> MessageExecutionReport {
+    let items: FixFieldList = scan_message_items(data).collect();
+    MessageExecutionReport::decode_items(&items).unwrap()
+}
+
+#[test]
+fn test_decode_extra_fields() {
+    let message = decode_execution_report(EXECUTION_REPORT);
+
+    assert_eq!(message.order_id, OrderID::new("ORD1".into()));
+    assert_eq!(
+        message.extra_fields,
+        vec![
+            FixExtraField::new(5001, b"VENUE"),
+            FixExtraField::new(9000, b"abc"),
+            FixExtraField::new(5000, b"x=y"),
+        ]
+    );
+}
+
+#[test]
+fn test_encode_extra_fields() -> anyhow::Result<()> {
+    let message = decode_execution_report(EXECUTION_REPORT);
+
+    let mut data = Vec::new();
+    message.encode_message(&mut data)?;
+
+    let expected = b"5001=VENUE\x019000=abc\x015000=x=y\x01";
+    assert!(data.windows(expected.len()).any(|x| x == &expected[..]));
+
+    assert_eq!(decode_execution_report(&data), message);
+    Ok(())
+}
+
+#[test]
+fn test_no_extra_fields() -> anyhow::Result<()> {
+    let data = b"35=8\x0149=BROKER\x0156=CLIENT\x0134=2\x0152=20210410-19:27:37.978\x01\
+37=ORD1\x0117=EXEC1\x01150=0\x0139=0\x0155=AAPL\x0154=1\x01151=100\x0114=0\x016=0\x01";
+    let message = decode_execution_report(data);
+    assert!(message.extra_fields.is_empty());
+
+    let mut encoded = Vec::new();
+    message.encode_message(&mut encoded)?;
+    assert_eq!(decode_execution_report(&encoded), message);
+    Ok(())
+}
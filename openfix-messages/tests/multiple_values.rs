@@ -0,0 +1,63 @@
+use openfix_messages::dec_helpers::scan_message_items;
+use openfix_messages::fix44::fields::*;
+use openfix_messages::fix44::messages::*;
+use openfix_messages::{AsFixMessage, FixFieldList, FixParseError, FromFixMessage};
+
+fn execution_report(exec_inst: &str) -> Vec<u8> {
+    format!(
+        "35=8\x0149=BROKER\x0156=CLIENT\x0134=2\x0152=20210410-19:27:37.978\x01\
+37=ORD1\x0117=EXEC1\x01150=0\x0139=0\x0155=AAPL\x0154=1\x01151=100\x0114=0\x016=0\x01\
+18={}\x01",
+        exec_inst
+    )
+    .into_bytes()
+}
+
+fn decode_execution_report(data: &[u8]) -> Result<MessageExecutionReport, FixParseError> {
+    let items: FixFieldList = scan_message_items(data).collect();
+    MessageExecutionReport::decode_items(&items)
+}
+
+#[test]
+fn test_decode_multiple_value_string() -> anyhow::Result<()> {
+    let message = decode_execution_report(&execution_report("1 5 G"))?;
+
+    assert_eq!(
+        message.exec_inst,
+        Some(ExecInst::new(vec![
+            ExecInstItem::NotHeld,
+            ExecInstItem::Held,
+            ExecInstItem::AllOrNone,
+        ]))
+    );
+
+    let mut data = Vec::new();
+    message.encode_message(&mut data)?;
+    let expected = b"\x0118=1 5 G\x01";
+    assert!(data.windows(expected.len()).any(|x| x == &expected[..]));
+
+    Ok(())
+}
+
+#[test]
+fn test_decode_multiple_value_string_single() -> anyhow::Result<()> {
+    let message = decode_execution_report(&execution_report("G"))?;
+    assert_eq!(
+        message.exec_inst,
+        Some(ExecInst::new(vec![ExecInstItem::AllOrNone]))
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "unknown_enum_values"))]
+fn test_decode_multiple_value_string_invalid() {
+    assert_eq!(
+        decode_execution_report(&execution_report("1 ?")),
+        Err(FixParseError::InvalidEnumValue(
+            openfix_messages::FixFieldContext::new(18, "ExecInst")
+                .with_parent("ExecutionReport")
+                .with_value(b"1 ?")
+        ))
+    );
+}
@@ -0,0 +1,152 @@
+use openfix_messages::dec_helpers::scan_message_items;
+use openfix_messages::fix44::fields::*;
+use openfix_messages::fix44::messages::*;
+use openfix_messages::{
+    AsFixMessage, FixExtraField, FixFieldContext, FixFieldList, FixParseError, FromFixMessage,
+};
+
+const EXECUTION_REPORT: &[u8] =
+    b"35=8\x0149=BROKER\x0156=CLIENT\x0134=2\x0152=20210410-19:27:37.978\x01\
+37=ORD1\x01\
+453=2\x01448=ALICE\x01447=D\x01452=1\x01802=2\x01523=SUB1\x01803=1\x01523=SUB2\x01803=2\x01\
+448=BOB\x01447=D\x01452=3\x01\
+17=EXEC1\x01150=0\x0139=0\x0155=AAPL\x0154=1\x01151=100\x0114=0\x016=0\x01";
+
+fn decode_execution_report(data: &[u8]) -> Result<MessageExecutionReport, FixParseError> {
+    let items: FixFieldList = scan_message_items(data).collect();
+    MessageExecutionReport::decode_items(&items)
+}
+
+#[test]
+fn test_decode_execution_report_parties() {
+    let message = decode_execution_report(EXECUTION_REPORT).unwrap();
+    let parties = message.parties.unwrap().no_party_i_ds;
+
+    assert_eq!(parties.len(), 2);
+
+    assert_eq!(parties[0].party_id, Some(PartyID::new("ALICE".into())));
+    assert_eq!(parties[0].party_role, Some(PartyRole::ExecutingFirm));
+    let sub_ids = &parties[0].ptys_sub_grp.as_ref().unwrap().no_party_sub_i_ds;
+    assert_eq!(sub_ids.len(), 2);
+    assert_eq!(
+        sub_ids[0].party_sub_id,
+        Some(PartySubID::new("SUB1".into()))
+    );
+    assert_eq!(
+        sub_ids[1].party_sub_id,
+        Some(PartySubID::new("SUB2".into()))
+    );
+
+    assert_eq!(parties[1].party_id, Some(PartyID::new("BOB".into())));
+    assert_eq!(parties[1].party_role, Some(PartyRole::ClientId));
+    assert_eq!(parties[1].ptys_sub_grp, None);
+
+    // Fields after group are still available
+    assert_eq!(message.exec_id, ExecID::new("EXEC1".into()));
+}
+
+#[test]
+fn test_encode_execution_report_parties() -> anyhow::Result<()> {
+    let message = decode_execution_report(EXECUTION_REPORT)?;
+
+    let mut data = Vec::new();
+    message.encode_message(&mut data)?;
+
+    let expected_parties =
+        b"453=2\x01448=ALICE\x01447=D\x01452=1\x01802=2\x01523=SUB1\x01803=1\x01523=SUB2\x01803=2\x01448=BOB\x01447=D\x01452=3\x01";
+    assert!(data
+        .windows(expected_parties.len())
+        .any(|x| x == &expected_parties[..]));
+
+    assert_eq!(decode_execution_report(&data)?, message);
+    Ok(())
+}
+
+#[test]
+fn test_decode_wrong_group_count() {
+    let data = b"35=8\x0149=BROKER\x0156=CLIENT\x0134=2\x0152=20210410-19:27:37.978\x01\
+37=ORD1\x01453=3\x01448=ALICE\x01448=BOB\x01\
+17=EXEC1\x01150=0\x0139=0\x0155=AAPL\x0154=1\x01151=100\x0114=0\x016=0\x01";
+
+    let err = decode_execution_report(data).unwrap_err();
+    assert_eq!(
+        err,
+        FixParseError::InvalidGroupCount {
+            context: FixFieldContext::new(453, "NoPartyIDs")
+                .with_parent("Parties")
+                .with_value(b"3"),
+            declared: 3,
+            actual: 2,
+        }
+    );
+    assert_eq!(err.session_reject_reason(), Some(16));
+    assert_eq!(err.ref_tag_id(), Some(453));
+}
+
+#[test]
+fn test_unknown_fields_in_group_entry() -> anyhow::Result<()> {
+    let data = b"35=8\x0149=BROKER\x0156=CLIENT\x0134=2\x0152=20210410-19:27:37.978\x01\
+37=ORD1\x01453=2\x01448=A\x019999=Z\x01448=B\x01\
+17=EXEC1\x01150=0\x0139=0\x0155=AAPL\x0154=1\x01151=100\x0114=0\x016=0\x015000=x\x01";
+
+    let message = decode_execution_report(data)?;
+    let parties = &message.parties.as_ref().unwrap().no_party_i_ds;
+    assert_eq!(parties.len(), 2);
+    assert_eq!(parties[0].party_id, Some(PartyID::new("A".into())));
+    assert_eq!(
+        parties[0].extra_fields,
+        vec![FixExtraField::new(9999, b"Z")]
+    );
+    assert_eq!(parties[1].party_id, Some(PartyID::new("B".into())));
+    assert!(parties[1].extra_fields.is_empty());
+    assert_eq!(message.extra_fields, vec![FixExtraField::new(5000, b"x")]);
+
+    // Unknown field is written back inside its entry
+    let mut encoded = Vec::new();
+    message.encode_message(&mut encoded)?;
+    let expected = b"453=2\x01448=A\x019999=Z\x01448=B\x01";
+    assert!(encoded.windows(expected.len()).any(|x| x == &expected[..]));
+
+    assert_eq!(decode_execution_report(&encoded)?, message);
+    Ok(())
+}
+
+#[test]
+fn test_decode_huge_group_count() {
+    let data = b"35=8\x0149=BROKER\x0156=CLIENT\x0134=2\x0152=20210410-19:27:37.978\x01\
+37=ORD1\x01453=4294967295\x01448=X\x01\
+17=EXEC1\x01150=0\x0139=0\x0155=AAPL\x0154=1\x01151=100\x0114=0\x016=0\x01";
+
+    assert_eq!(
+        decode_execution_report(data).unwrap_err(),
+        FixParseError::InvalidGroupCount {
+            context: FixFieldContext::new(453, "NoPartyIDs")
+                .with_parent("Parties")
+                .with_value(b"4294967295"),
+            declared: 4294967295,
+            actual: 1,
+        }
+    );
+}
+
+#[test]
+#[cfg(not(feature = "unknown_enum_values"))]
+fn test_decode_invalid_enum_context() {
+    let data = b"35=8\x0149=BROKER\x0156=CLIENT\x0134=2\x0152=20210410-19:27:37.978\x01\
+37=ORD1\x0117=EXEC1\x01150=0\x0139=X\x0155=AAPL\x0154=1\x01151=100\x0114=0\x016=0\x01";
+
+    let err = decode_execution_report(data).unwrap_err();
+    assert_eq!(
+        err,
+        FixParseError::InvalidEnumValue(
+            FixFieldContext::new(39, "OrdStatus")
+                .with_parent("ExecutionReport")
+                .with_value(b"X")
+        )
+    );
+    assert_eq!(err.session_reject_reason(), Some(5));
+    assert_eq!(
+        err.to_string(),
+        "field OrdStatus (39) in ExecutionReport with value \"X\" is not a valid enum value"
+    );
+}
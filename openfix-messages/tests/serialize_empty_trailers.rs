@@ -1,3 +1,4 @@
+use openfix_messages::chrono::{NaiveDate, TimeZone, Utc};
 use openfix_messages::enc_helpers::FixEnvelopeBuilder;
 use openfix_messages::test_spec::fields::*;
 use openfix_messages::test_spec::messages::*;
@@ -9,7 +10,14 @@ fn build_header() -> MessageHeader {
         sender_comp_id: SenderCompID::new("BROKER".into()),
         target_comp_id: TargetCompID::new("MARKET".into()),
         msg_seq_num: MsgSeqNum::new(23593),
-        sending_time: SendingTime::new(1618082857.9780622),
+        sending_time: SendingTime::new(
+            Utc.from_utc_datetime(
+                &NaiveDate::from_ymd_opt(2021, 4, 10)
+                    .unwrap()
+                    .and_hms_milli_opt(19, 27, 37, 978)
+                    .unwrap(),
+            ),
+        ),
         appl_ver_id: Some(ApplVerID::Fix42),
     }
 }
@@ -23,6 +31,7 @@ fn build_hb() -> MessageHeartbeat {
         header: build_header(),
         trailer: build_trailer(),
         test_req_id: None,
+        extra_fields: vec![],
     }
 }
 
@@ -39,7 +48,7 @@ fn test_serialize() -> anyhow::Result<()> {
 
     assert_eq!(
         data,
-        b"8=FIX.4.4\x019=63\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=1618082857.9780622\x011128=4\x0110=240\x01".to_vec()
+        b"8=FIX.4.4\x019=66\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=20210410-19:27:37.978\x011128=4\x0110=131\x01".to_vec()
     );
 
     Ok(())
@@ -1,3 +1,4 @@
+use openfix_messages::chrono::{NaiveDate, TimeZone, Utc};
 use openfix_messages::enc_helpers::FixEnvelopeBuilder;
 use openfix_messages::test_spec_sig::fields::*;
 use openfix_messages::test_spec_sig::messages::*;
@@ -9,7 +10,14 @@ fn build_header() -> MessageHeader {
         sender_comp_id: SenderCompID::new("BROKER".into()),
         target_comp_id: TargetCompID::new("MARKET".into()),
         msg_seq_num: MsgSeqNum::new(23593),
-        sending_time: SendingTime::new(1618082857.9780622),
+        sending_time: SendingTime::new(
+            Utc.from_utc_datetime(
+                &NaiveDate::from_ymd_opt(2021, 4, 10)
+                    .unwrap()
+                    .and_hms_milli_opt(19, 27, 37, 978)
+                    .unwrap(),
+            ),
+        ),
         appl_ver_id: Some(ApplVerID::Fix42),
     }
 }
@@ -24,8 +32,8 @@ fn build_empty_trailer() -> MessageTrailer {
 fn build_signed_trailer() -> MessageTrailer {
     let author = "arthurlm".to_string();
     MessageTrailer {
-        signature_length: Some(SignatureLength::new(author.len())),
-        signature: Some(Signature::new(author)),
+        signature_length: Some(SignatureLength::new(author.len() as u64)),
+        signature: Some(Signature::new(author.into_bytes())),
     }
 }
 
@@ -36,6 +44,7 @@ fn test_serialize_empty_trailer() -> anyhow::Result<()> {
         header: build_header(),
         trailer: build_empty_trailer(),
         test_req_id: None,
+        extra_fields: vec![],
     };
 
     let mut message_content = Vec::with_capacity(1024);
@@ -46,7 +55,7 @@ fn test_serialize_empty_trailer() -> anyhow::Result<()> {
 
     assert_eq!(
         data,
-        b"8=FIX.4.4\x019=63\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=1618082857.9780622\x011128=4\x0110=240\x01".to_vec()
+        b"8=FIX.4.4\x019=66\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=20210410-19:27:37.978\x011128=4\x0110=131\x01".to_vec()
     );
 
     Ok(())
@@ -59,6 +68,7 @@ fn test_serialize_signed_trailer() -> anyhow::Result<()> {
         header: build_header(),
         trailer: build_signed_trailer(),
         test_req_id: None,
+        extra_fields: vec![],
     };
 
     let mut message_content = Vec::with_capacity(1024);
@@ -69,8 +79,29 @@ fn test_serialize_signed_trailer() -> anyhow::Result<()> {
 
     assert_eq!(
         data,
-        b"8=FIX.4.4\x019=80\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=1618082857.9780622\x011128=4\x0193=8\x0189=arthurlm\x0110=239\x01".to_vec()
+        b"8=FIX.4.4\x019=83\x0135=0\x0149=BROKER\x0156=MARKET\x0134=23593\x0152=20210410-19:27:37.978\x011128=4\x0193=8\x0189=arthurlm\x0110=130\x01".to_vec()
     );
 
     Ok(())
 }
+
+#[test]
+fn test_serialize_binary_signature() -> anyhow::Result<()> {
+    let message = MessageHeartbeat {
+        header: build_header(),
+        trailer: MessageTrailer {
+            // Length is computed from signature when encoding
+            signature_length: None,
+            signature: Some(Signature::new(b"a\x01b\x01c".to_vec())),
+        },
+        test_req_id: None,
+        extra_fields: vec![],
+    };
+
+    let mut message_content = Vec::with_capacity(1024);
+    message.encode_message(&mut message_content)?;
+
+    assert!(message_content.ends_with(b"\x011128=4\x0193=5\x0189=a\x01b\x01c\x01"));
+
+    Ok(())
+}
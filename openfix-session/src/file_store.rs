@@ -0,0 +1,445 @@
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::MessageStore;
+
+const BODY_FILE: &str = "messages.body";
+const INDEX_FILE: &str = "messages.index";
+const SEQ_NUMS_FILE: &str = "seqnums";
+const SEQ_NUMS_TMP_FILE: &str = "seqnums.tmp";
+
+/// MsgSeqNum (34), offset, length and checksum of message in body file, as little endian u64
+const INDEX_RECORD_LEN: usize = 32;
+
+/// Next sender and target MsgSeqNum (34), as little endian u64
+const SEQ_NUMS_RECORD_LEN: usize = 16;
+
+/// When [`FileStore`] asks operating system to flush written data to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// Let operating system flush files: fastest, but last writes may be lost on power failure
+    Never,
+    /// Flush files after every write
+    Always,
+    /// Flush files on write, at most once per given interval
+    Interval(Duration),
+}
+
+/// Location of a message in body file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BodyRecord {
+    offset: u64,
+    len: u64,
+    /// Checksum of message bytes, as body may be overwritten after a crash
+    checksum: u64,
+}
+
+/// Crash-safe store, appending messages and sequence numbers to files of a directory.
+///
+/// Every file is append-only: records partially written before a crash are dropped when
+/// store is opened again. Files are compacted when store is reset.
+#[derive(Debug)]
+pub struct FileStore {
+    dir: PathBuf,
+    /// Encoded messages, one after the other
+    body: File,
+    /// Index records, locating messages in body file
+    index: File,
+    /// Sequence numbers records, the last one being the current one
+    seq_nums: File,
+    /// Location of messages in body file, by MsgSeqNum (34)
+    records: BTreeMap<u64, BodyRecord>,
+    body_len: u64,
+    next_sender_msg_seq_num: u64,
+    next_target_msg_seq_num: u64,
+    fsync_policy: FsyncPolicy,
+    last_sync: Option<Instant>,
+}
+
+impl FileStore {
+    /// Open store located in given directory, which is created if needed
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let body = open_append(&dir.join(BODY_FILE))?;
+        let body_len = body.metadata()?.len();
+
+        let mut index = open_append(&dir.join(INDEX_FILE))?;
+        let mut records = BTreeMap::new();
+        for record in read_records(&mut index, INDEX_RECORD_LEN)? {
+            let msg_seq_num = read_u64(&record[0..8]);
+            let record = BodyRecord {
+                offset: read_u64(&record[8..16]),
+                len: read_u64(&record[16..24]),
+                checksum: read_u64(&record[24..32]),
+            };
+
+            // Message may not have reached disk before index record
+            match record.offset.checked_add(record.len) {
+                Some(end) if end <= body_len => records.insert(msg_seq_num, record),
+                _ => records.remove(&msg_seq_num),
+            };
+        }
+
+        let mut seq_nums = open_append(&dir.join(SEQ_NUMS_FILE))?;
+        let (next_sender_msg_seq_num, next_target_msg_seq_num) =
+            match read_records(&mut seq_nums, SEQ_NUMS_RECORD_LEN)?.last() {
+                Some(record) => (read_u64(&record[0..8]), read_u64(&record[8..16])),
+                None => (1, 1),
+            };
+
+        let mut output = Self {
+            dir,
+            body,
+            index,
+            seq_nums,
+            records,
+            body_len,
+            next_sender_msg_seq_num,
+            next_target_msg_seq_num,
+            fsync_policy: FsyncPolicy::Never,
+            last_sync: None,
+        };
+
+        // Only last sequence numbers matter
+        output.compact_seq_nums()?;
+        Ok(output)
+    }
+
+    pub fn with_fsync_policy(mut self, fsync_policy: FsyncPolicy) -> Self {
+        self.fsync_policy = fsync_policy;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Flush every file to disk, whatever the fsync policy is
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.body.sync_data()?;
+        self.index.sync_data()?;
+        self.seq_nums.sync_data()?;
+        self.last_sync = Some(Instant::now());
+        Ok(())
+    }
+
+    fn sync_if_needed(&mut self) -> io::Result<()> {
+        let needed = match self.fsync_policy {
+            FsyncPolicy::Never => false,
+            FsyncPolicy::Always => true,
+            FsyncPolicy::Interval(interval) => match self.last_sync {
+                Some(last_sync) => last_sync.elapsed() >= interval,
+                None => true,
+            },
+        };
+
+        if needed {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    fn write_seq_nums(&mut self) -> io::Result<()> {
+        let record = seq_nums_record(self.next_sender_msg_seq_num, self.next_target_msg_seq_num);
+        self.seq_nums.write_all(&record)?;
+        self.sync_if_needed()
+    }
+
+    /// Replace sequence numbers file with a single record file
+    fn compact_seq_nums(&mut self) -> io::Result<()> {
+        let record = seq_nums_record(self.next_sender_msg_seq_num, self.next_target_msg_seq_num);
+        let tmp_path = self.dir.join(SEQ_NUMS_TMP_FILE);
+        let path = self.dir.join(SEQ_NUMS_FILE);
+
+        let mut tmp = File::create(&tmp_path)?;
+        tmp.write_all(&record)?;
+        tmp.sync_data()?;
+        fs::rename(&tmp_path, &path)?;
+
+        self.seq_nums = open_append(&path)?;
+        Ok(())
+    }
+}
+
+impl MessageStore for FileStore {
+    fn set(&mut self, msg_seq_num: u64, data: &[u8]) -> io::Result<()> {
+        let record = BodyRecord {
+            offset: self.body_len,
+            len: data.len() as u64,
+            checksum: checksum(data),
+        };
+        self.body.write_all(data)?;
+        self.body_len += record.len;
+
+        // Index record must never reach disk before message
+        if self.fsync_policy == FsyncPolicy::Always {
+            self.body.sync_data()?;
+        }
+
+        let mut index_record = [0; INDEX_RECORD_LEN];
+        index_record[0..8].copy_from_slice(&msg_seq_num.to_le_bytes());
+        index_record[8..16].copy_from_slice(&record.offset.to_le_bytes());
+        index_record[16..24].copy_from_slice(&record.len.to_le_bytes());
+        index_record[24..32].copy_from_slice(&record.checksum.to_le_bytes());
+        self.index.write_all(&index_record)?;
+
+        self.records.insert(msg_seq_num, record);
+        self.sync_if_needed()
+    }
+
+    fn get_range(&self, begin: u64, end: u64) -> io::Result<Vec<(u64, Vec<u8>)>> {
+        if begin > end {
+            return Ok(vec![]);
+        }
+
+        let mut body = &self.body;
+        let mut output = Vec::new();
+        for (msg_seq_num, record) in self.records.range(begin..=end) {
+            let mut data = vec![0; record.len as usize];
+            body.seek(SeekFrom::Start(record.offset))?;
+            body.read_exact(&mut data)?;
+
+            // Message overwritten after a crash is dropped, like a message missing from body
+            if checksum(&data) == record.checksum {
+                output.push((*msg_seq_num, data));
+            }
+        }
+        Ok(output)
+    }
+
+    fn next_sender_msg_seq_num(&self) -> u64 {
+        self.next_sender_msg_seq_num
+    }
+
+    fn next_target_msg_seq_num(&self) -> u64 {
+        self.next_target_msg_seq_num
+    }
+
+    fn set_next_sender_msg_seq_num(&mut self, value: u64) -> io::Result<()> {
+        self.next_sender_msg_seq_num = value;
+        self.write_seq_nums()
+    }
+
+    fn set_next_target_msg_seq_num(&mut self, value: u64) -> io::Result<()> {
+        self.next_target_msg_seq_num = value;
+        self.write_seq_nums()
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        // Index first: a crash must not leave index records pointing to dropped messages
+        self.index.set_len(0)?;
+        self.body.set_len(0)?;
+        self.records.clear();
+        self.body_len = 0;
+
+        self.next_sender_msg_seq_num = 1;
+        self.next_target_msg_seq_num = 1;
+        self.compact_seq_nums()?;
+
+        if self.fsync_policy != FsyncPolicy::Never {
+            self.sync()?;
+        }
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)
+}
+
+/// Read every complete record of file, and drop trailing partial one
+fn read_records(file: &mut File, record_len: usize) -> io::Result<Vec<Vec<u8>>> {
+    let mut data = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut data)?;
+
+    let complete_len = data.len() - data.len() % record_len;
+    if complete_len != data.len() {
+        file.set_len(complete_len as u64)?;
+    }
+
+    Ok(data[..complete_len]
+        .chunks_exact(record_len)
+        .map(|x| x.to_vec())
+        .collect())
+}
+
+/// FNV-1a hash of message bytes
+fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, x| {
+        (hash ^ u64::from(*x)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data.try_into().expect("record field is 8 bytes long"))
+}
+
+fn seq_nums_record(next_sender_msg_seq_num: u64, next_target_msg_seq_num: u64) -> Vec<u8> {
+    let mut record = Vec::with_capacity(SEQ_NUMS_RECORD_LEN);
+    record.extend_from_slice(&next_sender_msg_seq_num.to_le_bytes());
+    record.extend_from_slice(&next_target_msg_seq_num.to_le_bytes());
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_len(store: &FileStore, name: &str) -> u64 {
+        fs::metadata(store.dir().join(name)).unwrap().len()
+    }
+
+    fn append(store: &FileStore, name: &str, data: &[u8]) {
+        let mut file = open_append(&store.dir().join(name)).unwrap();
+        file.write_all(data).unwrap();
+    }
+
+    #[test]
+    fn test_reopen() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let mut store = FileStore::open(dir.path())?;
+        assert_eq!(store.next_sender_msg_seq_num(), 1);
+        assert_eq!(store.next_target_msg_seq_num(), 1);
+
+        store.set(2, b"two")?;
+        store.set(3, b"three")?;
+        store.set(2, b"TWO")?;
+        store.set_next_sender_msg_seq_num(4)?;
+        store.set_next_target_msg_seq_num(7)?;
+        drop(store);
+
+        let store = FileStore::open(dir.path())?;
+        assert_eq!(
+            store.get_range(1, 10)?,
+            vec![(2, b"TWO".to_vec()), (3, b"three".to_vec())]
+        );
+        assert_eq!(store.get_range(3, 2)?, vec![]);
+        assert_eq!(store.next_sender_msg_seq_num(), 4);
+        assert_eq!(store.next_target_msg_seq_num(), 7);
+
+        // Sequence numbers are compacted on open
+        assert_eq!(file_len(&store, SEQ_NUMS_FILE), 16);
+        Ok(())
+    }
+
+    #[test]
+    fn test_partial_writes() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let mut store = FileStore::open(dir.path())?;
+        store.set(1, b"one")?;
+        store.set_next_sender_msg_seq_num(2)?;
+
+        // Crash while writing index record of a message missing from body, and sequence numbers
+        let mut record = Vec::new();
+        record.extend_from_slice(&2_u64.to_le_bytes());
+        record.extend_from_slice(&3_u64.to_le_bytes());
+        record.extend_from_slice(&10_u64.to_le_bytes());
+        record.extend_from_slice(&0_u64.to_le_bytes());
+        append(&store, INDEX_FILE, &record);
+        append(&store, INDEX_FILE, &[3, 0, 0]);
+        append(&store, SEQ_NUMS_FILE, &[3, 0, 0, 0]);
+        drop(store);
+
+        let mut store = FileStore::open(dir.path())?;
+        assert_eq!(store.get_range(1, 10)?, vec![(1, b"one".to_vec())]);
+        assert_eq!(store.next_sender_msg_seq_num(), 2);
+        assert_eq!(file_len(&store, INDEX_FILE), 64);
+
+        // Next records are still aligned
+        store.set(2, b"two")?;
+        drop(store);
+        let store = FileStore::open(dir.path())?;
+        assert_eq!(
+            store.get_range(1, 10)?,
+            vec![(1, b"one".to_vec()), (2, b"two".to_vec())]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncated_body() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let mut store = FileStore::open(dir.path())?;
+        store.set(1, b"one")?;
+        store.set(2, b"two")?;
+        store.set(3, b"three")?;
+        drop(store);
+
+        // Power loss: index records reached disk, but not end of body
+        OpenOptions::new()
+            .write(true)
+            .open(dir.path().join(BODY_FILE))?
+            .set_len(4)?;
+
+        let mut store = FileStore::open(dir.path())?;
+        assert_eq!(store.get_range(1, 10)?, vec![(1, b"one".to_vec())]);
+
+        // New message overwrites body bytes still referenced by index record of message 2
+        store.set(4, b"FOUR")?;
+        drop(store);
+
+        let store = FileStore::open(dir.path())?;
+        assert_eq!(
+            store.get_range(1, 10)?,
+            vec![(1, b"one".to_vec()), (4, b"FOUR".to_vec())]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reset() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let mut store = FileStore::open(dir.path())?.with_fsync_policy(FsyncPolicy::Always);
+        store.set(1, b"one")?;
+        store.set_next_sender_msg_seq_num(2)?;
+        store.set_next_target_msg_seq_num(5)?;
+        store.reset()?;
+
+        assert_eq!(store.get_range(1, 10)?, vec![]);
+        assert_eq!(store.next_sender_msg_seq_num(), 1);
+        assert_eq!(store.next_target_msg_seq_num(), 1);
+        assert_eq!(file_len(&store, BODY_FILE), 0);
+        assert_eq!(file_len(&store, INDEX_FILE), 0);
+        assert_eq!(file_len(&store, SEQ_NUMS_FILE), 16);
+
+        store.set(1, b"new one")?;
+        drop(store);
+
+        let store = FileStore::open(dir.path())?;
+        assert_eq!(store.get_range(1, 10)?, vec![(1, b"new one".to_vec())]);
+        assert_eq!(store.next_target_msg_seq_num(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fsync_interval() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let mut store = FileStore::open(dir.path())?
+            .with_fsync_policy(FsyncPolicy::Interval(Duration::from_secs(60)));
+        assert!(store.last_sync.is_none());
+
+        store.set(1, b"one")?;
+        let last_sync = store.last_sync;
+        assert!(last_sync.is_some());
+
+        store.set(2, b"two")?;
+        assert_eq!(store.last_sync, last_sync);
+        Ok(())
+    }
+}
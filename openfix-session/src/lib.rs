@@ -0,0 +1,145 @@
+//! Sans-IO FIX 4.4 session layer.
+//!
+//! [`Session`] does not read nor write anything: it is fed with connection events, decoded
+//! messages received from counterparty and timer ticks, and returns [`SessionAction`]s to
+//! execute (messages to send, messages to process, disconnection).
+//!
+//! It handles:
+//! - Logon and HeartBtInt (108) negotiation,
+//! - Heartbeat, and TestRequest when counterparty is silent,
+//! - Logout handshake,
+//! - MsgSeqNum (34) gaps, with ResendRequest and SequenceReset-GapFill,
+//! - disconnection on protocol errors and timeouts.
+//!
+//! Sent messages and sequence numbers are kept in a [`MessageStore`], either in memory or in
+//! files to survive restarts.
+
+use openfix_messages::fix44::messages::Message;
+use std::time::Duration;
+use thiserror::Error;
+
+mod file_store;
+mod session;
+mod store;
+
+pub use crate::file_store::{FileStore, FsyncPolicy};
+pub use crate::session::Session;
+pub use crate::store::{MemoryStore, MessageStore};
+
+/// Which side opens the connection and sends the first Logon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionRole {
+    Initiator,
+    Acceptor,
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    pub role: SessionRole,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    /// Heartbeat interval sent in Logon by initiator (acceptor uses the received one)
+    pub heart_bt_int: Duration,
+    /// Reset sequence numbers at Logon, using ResetSeqNumFlag (141)
+    pub reset_seq_num: bool,
+    /// Maximum delay between connection and Logon completion
+    pub logon_timeout: Duration,
+    /// Maximum delay between Logout sending and counterparty Logout
+    pub logout_timeout: Duration,
+}
+
+impl SessionConfig {
+    pub fn new(role: SessionRole, sender_comp_id: &str, target_comp_id: &str) -> Self {
+        Self {
+            role,
+            sender_comp_id: sender_comp_id.to_string(),
+            target_comp_id: target_comp_id.to_string(),
+            heart_bt_int: Duration::from_secs(30),
+            reset_seq_num: false,
+            logon_timeout: Duration::from_secs(10),
+            logout_timeout: Duration::from_secs(10),
+        }
+    }
+
+    pub fn with_heart_bt_int(mut self, heart_bt_int: Duration) -> Self {
+        self.heart_bt_int = heart_bt_int;
+        self
+    }
+
+    pub fn with_reset_seq_num(mut self, reset_seq_num: bool) -> Self {
+        self.reset_seq_num = reset_seq_num;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// No connection, or connection must be closed
+    Disconnected,
+    /// Initiator sent Logon and waits for counterparty one
+    LogonSent,
+    /// Acceptor waits for counterparty Logon
+    AwaitingLogon,
+    /// Logon completed: application messages can be exchanged
+    Active,
+    /// Logout sent, waiting for counterparty one
+    LogoutSent,
+}
+
+/// Why connection has to be closed
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum DisconnectReason {
+    #[error("logout completed")]
+    Logout,
+
+    #[error("no logon received in time")]
+    LogonTimeout,
+
+    #[error("no logout received in time")]
+    LogoutTimeout,
+
+    #[error("no message received after test request")]
+    HeartbeatTimeout,
+
+    #[error("first message is not a logon: {0}")]
+    NotLogon(&'static str),
+
+    #[error("invalid logon: {0}")]
+    InvalidLogon(String),
+
+    #[error("unexpected logon")]
+    UnexpectedLogon,
+
+    #[error("CompID problem: expected sender {expected_sender:?} and target {expected_target:?}")]
+    InvalidCompId {
+        expected_sender: String,
+        expected_target: String,
+    },
+
+    #[error("MsgSeqNum too low, expecting {expected} but received {received}")]
+    MsgSeqNumTooLow { expected: u64, received: u64 },
+
+    #[error("message store failure: {0}")]
+    StoreFailure(String),
+}
+
+/// What the caller has to do after feeding the session
+#[derive(Debug, PartialEq)]
+pub enum SessionAction {
+    /// Encode message and write it to counterparty
+    Send(Box<Message>),
+    /// Process message received from counterparty, which is not handled by session
+    Receive(Box<Message>),
+    /// Logon completed
+    LoggedOn,
+    /// Logout completed, or session ended while being logged on
+    LoggedOut,
+    /// Close connection
+    Disconnect(DisconnectReason),
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SessionError {
+    #[error("session is not logged on")]
+    NotLoggedOn,
+}
@@ -0,0 +1,751 @@
+use openfix_messages::dec_helpers::{scan_message_items, FixEnvelopeValidator};
+use openfix_messages::enc_helpers::FixEnvelopeBuilder;
+use openfix_messages::fix44::fields::*;
+use openfix_messages::fix44::messages::*;
+use openfix_messages::{
+    AsFixMessage, AsFixMessageField, FixFieldList, FixSessionContext, MessageDest,
+};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::{
+    DisconnectReason, MemoryStore, MessageStore, SessionAction, SessionConfig, SessionError,
+    SessionRole, SessionState,
+};
+
+const ADMIN_FIELDS_SET: &str = "every required field of admin message is set";
+
+/// Part of heartbeat interval allowed as transmission time, before counterparty is considered silent
+const TRANSMISSION_TIME_DIVISOR: u32 = 5;
+
+/// EndSeqNo (16) value requesting every message up to the last sent one
+const END_SEQ_NO_INFINITY: u64 = 0;
+
+/// Outcome of received MsgSeqNum (34) check
+enum SeqNumStatus {
+    /// Message is the expected one
+    InOrder,
+    /// Messages are missing before this one, they have been requested
+    Gap,
+    /// Message has already been processed, or session has been closed
+    Ignored,
+}
+
+/// FIX session state machine.
+///
+/// Messages built with `with_session(&mut session)` get session CompIDs and the next sequence
+/// number, which is only consumed when message is given to [`Session::send`].
+#[derive(Debug)]
+pub struct Session<S = MemoryStore> {
+    config: SessionConfig,
+    state: SessionState,
+    /// Negotiated heartbeat interval
+    heart_bt_int: Duration,
+    /// Time of last state change, used for logon / logout timeouts
+    state_since: Option<Instant>,
+    last_sent: Option<Instant>,
+    last_received: Option<Instant>,
+    /// Sending time of unanswered TestRequest
+    pending_test_request: Option<Instant>,
+    test_request_count: u64,
+    /// Messages received after a gap, waiting for missing ones to be resent
+    queued_messages: BTreeMap<u64, Message>,
+    /// Last MsgSeqNum (34) covered by pending ResendRequest
+    resend_end: Option<u64>,
+    /// Sent application messages and sequence numbers
+    store: S,
+    /// First store failure of event being handled
+    store_error: Option<io::Error>,
+}
+
+impl Session {
+    /// Create session keeping messages and sequence numbers in memory
+    pub fn new(config: SessionConfig) -> Self {
+        Self::with_store(config, MemoryStore::new())
+    }
+}
+
+impl<S: MessageStore> Session<S> {
+    /// Create session restoring sequence numbers from store
+    pub fn with_store(config: SessionConfig, store: S) -> Self {
+        Self {
+            heart_bt_int: config.heart_bt_int,
+            config,
+            state: SessionState::Disconnected,
+            state_since: None,
+            last_sent: None,
+            last_received: None,
+            pending_test_request: None,
+            test_request_count: 0,
+            queued_messages: BTreeMap::new(),
+            resend_end: None,
+            store,
+            store_error: None,
+        }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    pub fn is_logged_on(&self) -> bool {
+        self.state == SessionState::Active
+    }
+
+    /// Heartbeat interval, as negotiated during Logon
+    pub fn heart_bt_int(&self) -> Duration {
+        self.heart_bt_int
+    }
+
+    /// MsgSeqNum (34) of next sent message
+    pub fn next_sender_msg_seq_num(&self) -> u64 {
+        self.store.next_sender_msg_seq_num()
+    }
+
+    /// Expected MsgSeqNum (34) of next received message
+    pub fn next_target_msg_seq_num(&self) -> u64 {
+        self.store.next_target_msg_seq_num()
+    }
+
+    /// Connection with counterparty is established
+    pub fn on_connected(&mut self, now: Instant) -> Vec<SessionAction> {
+        let mut actions = Vec::new();
+
+        self.heart_bt_int = self.config.heart_bt_int;
+        self.last_sent = None;
+        self.last_received = None;
+        self.pending_test_request = None;
+        if self.config.reset_seq_num {
+            self.reset_seq_nums();
+        }
+
+        match self.config.role {
+            SessionRole::Initiator => {
+                self.set_state(SessionState::LogonSent, now);
+                let logon = self.build_logon(self.config.reset_seq_num);
+                self.send_message(logon, now, &mut actions);
+            }
+            SessionRole::Acceptor => self.set_state(SessionState::AwaitingLogon, now),
+        }
+
+        self.disconnect_on_store_error(&mut actions);
+        actions
+    }
+
+    /// Connection has been closed, by counterparty or after a `Disconnect` action
+    pub fn on_disconnected(&mut self) -> Vec<SessionAction> {
+        let mut actions = Vec::new();
+        if self.has_logged_on() {
+            actions.push(SessionAction::LoggedOut);
+        }
+        self.set_disconnected();
+        actions
+    }
+
+    /// Handle message received from counterparty
+    pub fn on_message(&mut self, message: Message, now: Instant) -> Vec<SessionAction> {
+        let mut actions = Vec::new();
+        self.handle_message(message, now, &mut actions);
+        self.disconnect_on_store_error(&mut actions);
+        actions
+    }
+
+    /// Check timers: heartbeat, test request, logon and logout timeouts
+    pub fn on_tick(&mut self, now: Instant) -> Vec<SessionAction> {
+        let mut actions = Vec::new();
+        let state_elapsed = elapsed(self.state_since, now);
+
+        match self.state {
+            SessionState::LogonSent | SessionState::AwaitingLogon => {
+                if state_elapsed >= self.config.logon_timeout {
+                    self.disconnect(DisconnectReason::LogonTimeout, &mut actions);
+                }
+            }
+            SessionState::LogoutSent => {
+                if state_elapsed >= self.config.logout_timeout {
+                    self.disconnect(DisconnectReason::LogoutTimeout, &mut actions);
+                }
+            }
+            SessionState::Active => self.check_heartbeat(now, &mut actions),
+            SessionState::Disconnected => {}
+        }
+
+        self.disconnect_on_store_error(&mut actions);
+        actions
+    }
+
+    /// Send application message (or admin message not generated by session).
+    ///
+    /// MsgSeqNum (34) of message is set by session.
+    pub fn send(
+        &mut self,
+        message: Message,
+        now: Instant,
+    ) -> Result<Vec<SessionAction>, SessionError> {
+        if self.state != SessionState::Active {
+            return Err(SessionError::NotLoggedOn);
+        }
+
+        let mut actions = Vec::new();
+        self.send_message(message, now, &mut actions);
+        self.disconnect_on_store_error(&mut actions);
+        Ok(actions)
+    }
+
+    /// Start Logout handshake, or give up logon if it is not completed
+    pub fn logout(&mut self, text: Option<String>, now: Instant) -> Vec<SessionAction> {
+        let mut actions = Vec::new();
+
+        match self.state {
+            SessionState::Active => {
+                let logout = self.build_logout(text);
+                self.send_message(logout, now, &mut actions);
+                self.set_state(SessionState::LogoutSent, now);
+            }
+            SessionState::LogonSent | SessionState::AwaitingLogon => {
+                self.disconnect(DisconnectReason::Logout, &mut actions);
+            }
+            SessionState::LogoutSent | SessionState::Disconnected => {}
+        }
+
+        self.disconnect_on_store_error(&mut actions);
+        actions
+    }
+
+    fn handle_message(&mut self, message: Message, now: Instant, actions: &mut Vec<SessionAction>) {
+        if self.state == SessionState::Disconnected {
+            return;
+        }
+
+        // Any message proves counterparty is alive
+        self.last_received = Some(now);
+        self.pending_test_request = None;
+
+        let header = message.header();
+        if header.sender_comp_id.value != self.config.target_comp_id
+            || header.target_comp_id.value != self.config.sender_comp_id
+        {
+            let reason = DisconnectReason::InvalidCompId {
+                expected_sender: self.config.target_comp_id.clone(),
+                expected_target: self.config.sender_comp_id.clone(),
+            };
+            return self.logout_and_disconnect(reason, now, actions);
+        }
+
+        match self.state {
+            SessionState::LogonSent | SessionState::AwaitingLogon => match message {
+                Message::Logon(logon) => self.on_logon(logon, now, actions),
+                message => {
+                    let reason = DisconnectReason::NotLogon(message.msg_type());
+                    self.disconnect(reason, actions);
+                }
+            },
+            SessionState::Active | SessionState::LogoutSent => {
+                self.on_sequenced_message(message, now, actions)
+            }
+            SessionState::Disconnected => {}
+        }
+    }
+
+    fn on_logon(&mut self, logon: MessageLogon, now: Instant, actions: &mut Vec<SessionAction>) {
+        if logon.encrypt_method != EncryptMethod::None {
+            let reason = DisconnectReason::InvalidLogon("unsupported EncryptMethod".to_string());
+            return self.logout_and_disconnect(reason, now, actions);
+        }
+        let heart_bt_int = match u64::try_from(logon.heart_bt_int.value) {
+            Ok(heart_bt_int) => Duration::from_secs(heart_bt_int),
+            Err(_) => {
+                let reason = DisconnectReason::InvalidLogon("negative HeartBtInt".to_string());
+                return self.logout_and_disconnect(reason, now, actions);
+            }
+        };
+
+        let reset_seq_num = matches!(logon.reset_seq_num_flag, Some(ResetSeqNumFlag::Yes));
+        if reset_seq_num && self.config.role == SessionRole::Acceptor {
+            self.reset_seq_nums();
+        }
+
+        let expected = self.next_target_msg_seq_num();
+        let received = logon.header.msg_seq_num.value;
+        if received < expected {
+            let reason = DisconnectReason::MsgSeqNumTooLow { expected, received };
+            return self.logout_and_disconnect(reason, now, actions);
+        }
+
+        self.heart_bt_int = heart_bt_int;
+        if self.config.role == SessionRole::Acceptor {
+            let logon = self.build_logon(reset_seq_num || self.config.reset_seq_num);
+            self.send_message(logon, now, actions);
+        }
+        self.set_state(SessionState::Active, now);
+        actions.push(SessionAction::LoggedOn);
+
+        // Logon is processed anyway, missing messages are requested after it
+        if received == expected {
+            self.set_next_target_msg_seq_num(self.next_target_msg_seq_num() + 1);
+        } else {
+            self.request_resend(received, now, actions);
+        }
+    }
+
+    /// Handle message received once logged on, according to its MsgSeqNum (34)
+    fn on_sequenced_message(
+        &mut self,
+        message: Message,
+        now: Instant,
+        actions: &mut Vec<SessionAction>,
+    ) {
+        // SequenceReset-Reset ignores MsgSeqNum (34)
+        let message = match message {
+            Message::SequenceReset(sequence_reset)
+                if sequence_reset.gap_fill_flag != Some(GapFillFlag::Yes) =>
+            {
+                self.on_sequence_reset(sequence_reset, now, actions);
+                return self.process_queued_messages(now, actions);
+            }
+            message => message,
+        };
+
+        match self.check_msg_seq_num(message.header(), now, actions) {
+            SeqNumStatus::InOrder => {
+                self.on_session_message(message, now, actions);
+                self.process_queued_messages(now, actions);
+            }
+            SeqNumStatus::Gap => match message {
+                // Answer counterparty before it resends what we are missing
+                Message::ResendRequest(_) | Message::Logout(_) => {
+                    self.on_session_message(message, now, actions)
+                }
+                message => {
+                    let msg_seq_num = message.header().msg_seq_num.value;
+                    self.queued_messages.insert(msg_seq_num, message);
+                }
+            },
+            SeqNumStatus::Ignored => {}
+        }
+    }
+
+    fn on_session_message(
+        &mut self,
+        message: Message,
+        now: Instant,
+        actions: &mut Vec<SessionAction>,
+    ) {
+        match message {
+            Message::Heartbeat(_) => {}
+            Message::TestRequest(test_request) => {
+                let heartbeat = self.build_heartbeat(Some(test_request.test_req_id));
+                self.send_message(heartbeat, now, actions);
+            }
+            Message::Logout(_) => {
+                if self.state == SessionState::Active {
+                    let logout = self.build_logout(None);
+                    self.send_message(logout, now, actions);
+                }
+                self.disconnect(DisconnectReason::Logout, actions);
+            }
+            Message::Logon(_) => {
+                self.logout_and_disconnect(DisconnectReason::UnexpectedLogon, now, actions)
+            }
+            Message::ResendRequest(resend_request) => {
+                self.on_resend_request(resend_request, now, actions)
+            }
+            Message::SequenceReset(sequence_reset) => {
+                self.on_sequence_reset(sequence_reset, now, actions)
+            }
+            message => actions.push(SessionAction::Receive(Box::new(message))),
+        }
+    }
+
+    /// Check received MsgSeqNum (34), and request missing messages if there is a gap
+    fn check_msg_seq_num(
+        &mut self,
+        header: &MessageHeader,
+        now: Instant,
+        actions: &mut Vec<SessionAction>,
+    ) -> SeqNumStatus {
+        let expected = self.next_target_msg_seq_num();
+        let received = header.msg_seq_num.value;
+
+        if received == expected {
+            self.set_next_target_msg_seq_num(self.next_target_msg_seq_num() + 1);
+            SeqNumStatus::InOrder
+        } else if received < expected {
+            // Possible duplicates have already been processed
+            if header.poss_dup_flag != Some(PossDupFlag::Yes) {
+                let reason = DisconnectReason::MsgSeqNumTooLow { expected, received };
+                self.logout_and_disconnect(reason, now, actions);
+            }
+            SeqNumStatus::Ignored
+        } else {
+            self.request_resend(received, now, actions);
+            SeqNumStatus::Gap
+        }
+    }
+
+    /// Send ResendRequest from expected MsgSeqNum (34), unless one is already pending
+    fn request_resend(&mut self, received: u64, now: Instant, actions: &mut Vec<SessionAction>) {
+        if self.resend_end.is_some() {
+            return;
+        }
+
+        let resend_request = self.build_resend_request(self.next_target_msg_seq_num());
+        self.send_message(resend_request, now, actions);
+        self.resend_end = Some(received);
+    }
+
+    /// Process queued messages following expected MsgSeqNum (34)
+    fn process_queued_messages(&mut self, now: Instant, actions: &mut Vec<SessionAction>) {
+        loop {
+            // Drop messages covered by a SequenceReset
+            self.queued_messages = self
+                .queued_messages
+                .split_off(&self.next_target_msg_seq_num());
+
+            if !self.has_logged_on() {
+                return;
+            }
+            match self.queued_messages.remove(&self.next_target_msg_seq_num()) {
+                Some(message) => {
+                    self.set_next_target_msg_seq_num(self.next_target_msg_seq_num() + 1);
+                    self.on_session_message(message, now, actions);
+                }
+                None => break,
+            }
+        }
+
+        match self.resend_end {
+            Some(resend_end) if self.next_target_msg_seq_num() > resend_end => {
+                self.resend_end = None;
+                // Messages sent after ResendRequest may still be missing
+                if let Some(&received) = self.queued_messages.keys().next_back() {
+                    self.request_resend(received, now, actions);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle SequenceReset, both GapFill and Reset modes
+    fn on_sequence_reset(
+        &mut self,
+        sequence_reset: MessageSequenceReset,
+        now: Instant,
+        actions: &mut Vec<SessionAction>,
+    ) {
+        let new_seq_no = sequence_reset.new_seq_no.value;
+        if new_seq_no < self.next_target_msg_seq_num() {
+            let reject = self.build_reject(
+                sequence_reset.header.msg_seq_num.value,
+                MessageSequenceReset::MESSAGE_TYPE,
+                NewSeqNo::FIX_KEY,
+                SessionRejectReason::ValueIsIncorrect,
+                format!(
+                    "NewSeqNo {} is lower than expected MsgSeqNum {}",
+                    new_seq_no,
+                    self.next_target_msg_seq_num()
+                ),
+            );
+            self.send_message(reject, now, actions);
+        } else {
+            self.set_next_target_msg_seq_num(new_seq_no);
+        }
+    }
+
+    /// Resend stored application messages, and fill gaps left by admin messages
+    fn on_resend_request(
+        &mut self,
+        resend_request: MessageResendRequest,
+        now: Instant,
+        actions: &mut Vec<SessionAction>,
+    ) {
+        let last_sent = self.next_sender_msg_seq_num() - 1;
+        let begin = resend_request.begin_seq_no.value.max(1);
+        let end = match resend_request.end_seq_no.value {
+            END_SEQ_NO_INFINITY => last_sent,
+            end => end.min(last_sent),
+        };
+        if begin > end {
+            return;
+        }
+
+        let stored = match self.store.get_range(begin, end) {
+            Ok(stored) => stored,
+            Err(err) => return self.check_store(Err(err)),
+        };
+        let stored: Vec<_> = stored
+            .into_iter()
+            .filter_map(|(msg_seq_num, data)| Some((msg_seq_num, decode_frame(&data)?)))
+            .collect();
+
+        let mut next = begin;
+        for (msg_seq_num, message) in stored {
+            if msg_seq_num > next {
+                let gap_fill = self.build_gap_fill(next, msg_seq_num);
+                self.resend_message(gap_fill, now, actions);
+            }
+            self.resend_message(message, now, actions);
+            next = msg_seq_num + 1;
+        }
+        if next <= end {
+            let gap_fill = self.build_gap_fill(next, end + 1);
+            self.resend_message(gap_fill, now, actions);
+        }
+    }
+
+    fn check_heartbeat(&mut self, now: Instant, actions: &mut Vec<SessionAction>) {
+        // Heartbeats are disabled when interval is 0
+        if self.heart_bt_int == Duration::from_secs(0) {
+            return;
+        }
+        let timeout = self.heart_bt_int + self.heart_bt_int / TRANSMISSION_TIME_DIVISOR;
+
+        match self.pending_test_request {
+            Some(sent) if elapsed(Some(sent), now) >= timeout => {
+                return self.disconnect(DisconnectReason::HeartbeatTimeout, actions);
+            }
+            Some(_) => {}
+            None if elapsed(self.last_received, now) >= timeout => {
+                self.test_request_count += 1;
+                let test_request = self.build_test_request(self.test_request_count.to_string());
+                self.send_message(test_request, now, actions);
+                self.pending_test_request = Some(now);
+            }
+            None => {}
+        }
+
+        if elapsed(self.last_sent, now) >= self.heart_bt_int {
+            let heartbeat = self.build_heartbeat(None);
+            self.send_message(heartbeat, now, actions);
+        }
+    }
+
+    fn send_message(
+        &mut self,
+        mut message: Message,
+        now: Instant,
+        actions: &mut Vec<SessionAction>,
+    ) {
+        let msg_seq_num = self.next_sender_msg_seq_num();
+        message.header_mut().msg_seq_num = MsgSeqNum::new(msg_seq_num);
+        if message.dest() == MessageDest::App {
+            // Message that cannot be encoded is never sent, it is gap filled on resend
+            if let Ok(data) = encode_frame(&message) {
+                let result = self.store.set(msg_seq_num, &data);
+                self.check_store(result);
+            }
+        }
+        let result = self.store.set_next_sender_msg_seq_num(msg_seq_num + 1);
+        self.check_store(result);
+        self.last_sent = Some(now);
+        actions.push(SessionAction::Send(Box::new(message)));
+    }
+
+    /// Send again message with its original MsgSeqNum (34), as a possible duplicate
+    fn resend_message(
+        &mut self,
+        mut message: Message,
+        now: Instant,
+        actions: &mut Vec<SessionAction>,
+    ) {
+        let sending_time = self.sending_time();
+        let header = message.header_mut();
+        header.poss_dup_flag = Some(PossDupFlag::Yes);
+        header.orig_sending_time = Some(OrigSendingTime::new(header.sending_time.value));
+        header.sending_time = SendingTime::new(sending_time);
+
+        self.last_sent = Some(now);
+        actions.push(SessionAction::Send(Box::new(message)));
+    }
+
+    fn logout_and_disconnect(
+        &mut self,
+        reason: DisconnectReason,
+        now: Instant,
+        actions: &mut Vec<SessionAction>,
+    ) {
+        let logout = self.build_logout(Some(reason.to_string()));
+        self.send_message(logout, now, actions);
+        self.disconnect(reason, actions);
+    }
+
+    fn disconnect(&mut self, reason: DisconnectReason, actions: &mut Vec<SessionAction>) {
+        if self.has_logged_on() {
+            actions.push(SessionAction::LoggedOut);
+        }
+        self.set_disconnected();
+        actions.push(SessionAction::Disconnect(reason));
+    }
+
+    fn set_next_target_msg_seq_num(&mut self, value: u64) {
+        let result = self.store.set_next_target_msg_seq_num(value);
+        self.check_store(result);
+    }
+
+    /// Keep first store failure, session is disconnected once current event is handled
+    fn check_store(&mut self, result: io::Result<()>) {
+        if let Err(err) = result {
+            self.store_error.get_or_insert(err);
+        }
+    }
+
+    fn disconnect_on_store_error(&mut self, actions: &mut Vec<SessionAction>) {
+        if let Some(err) = self.store_error.take() {
+            if self.state != SessionState::Disconnected {
+                self.disconnect(DisconnectReason::StoreFailure(err.to_string()), actions);
+            }
+        }
+    }
+
+    fn has_logged_on(&self) -> bool {
+        matches!(self.state, SessionState::Active | SessionState::LogoutSent)
+    }
+
+    fn set_state(&mut self, state: SessionState, now: Instant) {
+        self.state = state;
+        self.state_since = Some(now);
+    }
+
+    fn set_disconnected(&mut self) {
+        self.state = SessionState::Disconnected;
+        self.state_since = None;
+        self.pending_test_request = None;
+        self.queued_messages.clear();
+        self.resend_end = None;
+    }
+
+    fn reset_seq_nums(&mut self) {
+        let result = self.store.reset();
+        self.check_store(result);
+        self.queued_messages.clear();
+        self.resend_end = None;
+    }
+
+    fn build_logon(&mut self, reset_seq_num: bool) -> Message {
+        let heart_bt_int = i32::try_from(self.heart_bt_int.as_secs()).unwrap_or(i32::MAX);
+        let mut builder = MessageLogon::builder()
+            .with_session(self)
+            .encrypt_method(EncryptMethod::None)
+            .heart_bt_int(HeartBtInt::new(heart_bt_int));
+        if reset_seq_num {
+            builder = builder.reset_seq_num_flag(ResetSeqNumFlag::Yes);
+        }
+        Message::Logon(builder.build().expect(ADMIN_FIELDS_SET))
+    }
+
+    fn build_heartbeat(&mut self, test_req_id: Option<TestReqID>) -> Message {
+        let mut builder = MessageHeartbeat::builder().with_session(self);
+        if let Some(test_req_id) = test_req_id {
+            builder = builder.test_req_id(test_req_id);
+        }
+        Message::Heartbeat(builder.build().expect(ADMIN_FIELDS_SET))
+    }
+
+    fn build_test_request(&mut self, test_req_id: String) -> Message {
+        let test_request = MessageTestRequest::builder()
+            .with_session(self)
+            .test_req_id(TestReqID::new(test_req_id))
+            .build()
+            .expect(ADMIN_FIELDS_SET);
+        Message::TestRequest(test_request)
+    }
+
+    fn build_logout(&mut self, text: Option<String>) -> Message {
+        let mut builder = MessageLogout::builder().with_session(self);
+        if let Some(text) = text {
+            builder = builder.text(Text::new(text));
+        }
+        Message::Logout(builder.build().expect(ADMIN_FIELDS_SET))
+    }
+
+    fn build_resend_request(&mut self, begin_seq_no: u64) -> Message {
+        let resend_request = MessageResendRequest::builder()
+            .with_session(self)
+            .begin_seq_no(BeginSeqNo::new(begin_seq_no))
+            .end_seq_no(EndSeqNo::new(END_SEQ_NO_INFINITY))
+            .build()
+            .expect(ADMIN_FIELDS_SET);
+        Message::ResendRequest(resend_request)
+    }
+
+    /// SequenceReset-GapFill replacing messages from `msg_seq_num` to `new_seq_no` (excluded)
+    fn build_gap_fill(&mut self, msg_seq_num: u64, new_seq_no: u64) -> Message {
+        let mut gap_fill = MessageSequenceReset::builder()
+            .with_session(self)
+            .gap_fill_flag(GapFillFlag::Yes)
+            .new_seq_no(NewSeqNo::new(new_seq_no))
+            .build()
+            .expect(ADMIN_FIELDS_SET);
+        gap_fill.header.msg_seq_num = MsgSeqNum::new(msg_seq_num);
+        Message::SequenceReset(gap_fill)
+    }
+
+    fn build_reject(
+        &mut self,
+        ref_seq_num: u64,
+        ref_msg_type: &str,
+        ref_tag_id: u32,
+        reason: SessionRejectReason,
+        text: String,
+    ) -> Message {
+        let reject = MessageReject::builder()
+            .with_session(self)
+            .ref_seq_num(RefSeqNum::new(ref_seq_num))
+            .ref_tag_id(RefTagID::new(ref_tag_id as i32))
+            .ref_msg_type(RefMsgType::new(ref_msg_type.to_string()))
+            .session_reject_reason(reason)
+            .text(Text::new(text))
+            .build()
+            .expect(ADMIN_FIELDS_SET);
+        Message::Reject(reject)
+    }
+}
+
+impl<S: MessageStore> FixSessionContext for Session<S> {
+    fn sender_comp_id(&self) -> &str {
+        &self.config.sender_comp_id
+    }
+
+    fn target_comp_id(&self) -> &str {
+        &self.config.target_comp_id
+    }
+
+    /// Sequence number is only consumed when message is sent
+    fn next_msg_seq_num(&mut self) -> u64 {
+        self.store.next_sender_msg_seq_num()
+    }
+}
+
+/// Encode message as it is written to counterparty
+fn encode_frame(message: &Message) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    message.encode_message(&mut body)?;
+
+    let mut data = Vec::new();
+    FixEnvelopeBuilder::new().build_message(&mut data, &body)?;
+    Ok(data)
+}
+
+fn decode_frame(data: &[u8]) -> Option<Message> {
+    let body = FixEnvelopeValidator::new().validate_message(data).ok()?;
+    let items: FixFieldList = scan_message_items(body)
+        .with_data_fields(DATA_FIELDS)
+        .collect();
+    Message::decode_any(&items).ok()
+}
+
+fn elapsed(since: Option<Instant>, now: Instant) -> Duration {
+    since.map_or(Duration::from_secs(0), |since| {
+        now.saturating_duration_since(since)
+    })
+}
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::io;
+
+/// Storage of session state which must survive restarts: encoded messages sent to
+/// counterparty, to answer ResendRequest, and next sequence numbers.
+///
+/// Messages are stored as written on the wire, ie: what `FixEnvelopeBuilder::build_message`
+/// produces.
+pub trait MessageStore {
+    /// Store message sent with given MsgSeqNum (34), replacing previous one if any
+    fn set(&mut self, msg_seq_num: u64, data: &[u8]) -> io::Result<()>;
+
+    /// Get stored messages from `begin` to `end` (both included), ordered by MsgSeqNum (34).
+    ///
+    /// Sequence numbers without stored message (ie: admin messages) are skipped.
+    fn get_range(&self, begin: u64, end: u64) -> io::Result<Vec<(u64, Vec<u8>)>>;
+
+    fn next_sender_msg_seq_num(&self) -> u64;
+
+    fn next_target_msg_seq_num(&self) -> u64;
+
+    fn set_next_sender_msg_seq_num(&mut self, value: u64) -> io::Result<()>;
+
+    fn set_next_target_msg_seq_num(&mut self, value: u64) -> io::Result<()>;
+
+    /// Remove every stored message and restart sequence numbers from 1
+    fn reset(&mut self) -> io::Result<()>;
+}
+
+/// Store keeping everything in memory, which is lost when process exits
+#[derive(Debug)]
+pub struct MemoryStore {
+    messages: BTreeMap<u64, Vec<u8>>,
+    next_sender_msg_seq_num: u64,
+    next_target_msg_seq_num: u64,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self {
+            messages: BTreeMap::new(),
+            next_sender_msg_seq_num: 1,
+            next_target_msg_seq_num: 1,
+        }
+    }
+}
+
+impl MessageStore for MemoryStore {
+    fn set(&mut self, msg_seq_num: u64, data: &[u8]) -> io::Result<()> {
+        self.messages.insert(msg_seq_num, data.to_vec());
+        Ok(())
+    }
+
+    fn get_range(&self, begin: u64, end: u64) -> io::Result<Vec<(u64, Vec<u8>)>> {
+        if begin > end {
+            return Ok(vec![]);
+        }
+
+        Ok(self
+            .messages
+            .range(begin..=end)
+            .map(|(msg_seq_num, data)| (*msg_seq_num, data.clone()))
+            .collect())
+    }
+
+    fn next_sender_msg_seq_num(&self) -> u64 {
+        self.next_sender_msg_seq_num
+    }
+
+    fn next_target_msg_seq_num(&self) -> u64 {
+        self.next_target_msg_seq_num
+    }
+
+    fn set_next_sender_msg_seq_num(&mut self, value: u64) -> io::Result<()> {
+        self.next_sender_msg_seq_num = value;
+        Ok(())
+    }
+
+    fn set_next_target_msg_seq_num(&mut self, value: u64) -> io::Result<()> {
+        self.next_target_msg_seq_num = value;
+        Ok(())
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        *self = Self::new();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_store() -> io::Result<()> {
+        let mut store = MemoryStore::new();
+        assert_eq!(store.next_sender_msg_seq_num(), 1);
+        assert_eq!(store.next_target_msg_seq_num(), 1);
+
+        store.set(2, b"two")?;
+        store.set(4, b"four")?;
+        store.set(5, b"five")?;
+        store.set_next_sender_msg_seq_num(6)?;
+        store.set_next_target_msg_seq_num(3)?;
+
+        assert_eq!(
+            store.get_range(1, 4)?,
+            vec![(2, b"two".to_vec()), (4, b"four".to_vec())]
+        );
+        assert_eq!(store.get_range(5, 4)?, vec![]);
+        assert_eq!(store.next_sender_msg_seq_num(), 6);
+        assert_eq!(store.next_target_msg_seq_num(), 3);
+
+        store.reset()?;
+        assert_eq!(store.get_range(1, 10)?, vec![]);
+        assert_eq!(store.next_sender_msg_seq_num(), 1);
+        assert_eq!(store.next_target_msg_seq_num(), 1);
+        Ok(())
+    }
+}
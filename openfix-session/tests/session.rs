@@ -0,0 +1,804 @@
+use openfix_messages::fix44::fields::*;
+use openfix_messages::fix44::messages::*;
+use openfix_messages::FixSessionContext;
+use openfix_session::*;
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Counterparty of tested session, building inbound messages
+struct Broker {
+    next_seq_num: u64,
+}
+
+impl FixSessionContext for Broker {
+    fn sender_comp_id(&self) -> &str {
+        "BROKER"
+    }
+
+    fn target_comp_id(&self) -> &str {
+        "CLIENT"
+    }
+
+    fn next_msg_seq_num(&mut self) -> u64 {
+        self.next_seq_num += 1;
+        self.next_seq_num - 1
+    }
+}
+
+impl Broker {
+    fn new() -> Self {
+        Self { next_seq_num: 1 }
+    }
+
+    fn logon(&mut self, heart_bt_int: i32) -> Message {
+        Message::Logon(
+            MessageLogon::builder()
+                .with_session(self)
+                .encrypt_method(EncryptMethod::None)
+                .heart_bt_int(HeartBtInt::new(heart_bt_int))
+                .build()
+                .unwrap(),
+        )
+    }
+
+    fn heartbeat(&mut self) -> Message {
+        Message::Heartbeat(
+            MessageHeartbeat::builder()
+                .with_session(self)
+                .build()
+                .unwrap(),
+        )
+    }
+
+    fn test_request(&mut self, id: &str) -> Message {
+        Message::TestRequest(
+            MessageTestRequest::builder()
+                .with_session(self)
+                .test_req_id(TestReqID::new(id.to_string()))
+                .build()
+                .unwrap(),
+        )
+    }
+
+    fn resend_request(&mut self, begin_seq_no: u64, end_seq_no: u64) -> Message {
+        Message::ResendRequest(
+            MessageResendRequest::builder()
+                .with_session(self)
+                .begin_seq_no(BeginSeqNo::new(begin_seq_no))
+                .end_seq_no(EndSeqNo::new(end_seq_no))
+                .build()
+                .unwrap(),
+        )
+    }
+
+    fn sequence_reset(&mut self, gap_fill: bool, new_seq_no: u64) -> Message {
+        let mut builder = MessageSequenceReset::builder()
+            .with_session(self)
+            .new_seq_no(NewSeqNo::new(new_seq_no));
+        if gap_fill {
+            builder = builder.gap_fill_flag(GapFillFlag::Yes);
+        }
+        Message::SequenceReset(builder.build().unwrap())
+    }
+
+    fn logout(&mut self) -> Message {
+        Message::Logout(MessageLogout::builder().with_session(self).build().unwrap())
+    }
+
+    fn list_status_request(&mut self) -> Message {
+        Message::ListStatusRequest(
+            MessageListStatusRequest::builder()
+                .with_session(self)
+                .list_id(ListID::new("LIST1".to_string()))
+                .build()
+                .unwrap(),
+        )
+    }
+}
+
+fn config(role: SessionRole) -> SessionConfig {
+    SessionConfig::new(role, "CLIENT", "BROKER").with_heart_bt_int(Duration::from_secs(10))
+}
+
+fn sent(actions: &[SessionAction]) -> Vec<&Message> {
+    actions
+        .iter()
+        .filter_map(|action| match action {
+            SessionAction::Send(message) => Some(message.as_ref()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn poss_dup(mut message: Message) -> Message {
+    let header = message.header_mut();
+    header.poss_dup_flag = Some(PossDupFlag::Yes);
+    header.orig_sending_time = Some(OrigSendingTime::new(header.sending_time.value));
+    message
+}
+
+fn received(actions: &[SessionAction]) -> Vec<u64> {
+    actions
+        .iter()
+        .filter_map(|action| match action {
+            SessionAction::Receive(message) => Some(message.header().msg_seq_num.value),
+            _ => None,
+        })
+        .collect()
+}
+
+fn secs(start: Instant, secs: u64) -> Instant {
+    start + Duration::from_secs(secs)
+}
+
+/// Initiator session logged on with broker at `start`
+fn logged_on(start: Instant) -> (Session, Broker) {
+    let mut session = Session::new(config(SessionRole::Initiator));
+    let mut broker = Broker::new();
+    session.on_connected(start);
+    let actions = session.on_message(broker.logon(10), start);
+    assert_eq!(actions, vec![SessionAction::LoggedOn]);
+    (session, broker)
+}
+
+#[test]
+fn test_initiator_logon() {
+    let start = Instant::now();
+    let mut session =
+        Session::new(config(SessionRole::Initiator).with_heart_bt_int(Duration::from_secs(20)));
+    let mut broker = Broker::new();
+    assert_eq!(session.state(), SessionState::Disconnected);
+
+    let actions = session.on_connected(start);
+    assert_eq!(session.state(), SessionState::LogonSent);
+    match sent(&actions).as_slice() {
+        [Message::Logon(logon)] => {
+            assert_eq!(logon.header.sender_comp_id.value, "CLIENT");
+            assert_eq!(logon.header.target_comp_id.value, "BROKER");
+            assert_eq!(logon.header.msg_seq_num.value, 1);
+            assert_eq!(logon.heart_bt_int.value, 20);
+            assert_eq!(logon.reset_seq_num_flag, None);
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+
+    let actions = session.on_message(broker.logon(20), start);
+    assert_eq!(actions, vec![SessionAction::LoggedOn]);
+    assert!(session.is_logged_on());
+    assert_eq!(session.heart_bt_int(), Duration::from_secs(20));
+    assert_eq!(session.next_sender_msg_seq_num(), 2);
+    assert_eq!(session.next_target_msg_seq_num(), 2);
+}
+
+#[test]
+fn test_acceptor_logon() {
+    let start = Instant::now();
+    let mut session = Session::new(config(SessionRole::Acceptor));
+    let mut broker = Broker::new();
+
+    assert_eq!(session.on_connected(start), vec![]);
+    assert_eq!(session.state(), SessionState::AwaitingLogon);
+
+    let actions = session.on_message(broker.logon(45), start);
+    assert_eq!(actions.last(), Some(&SessionAction::LoggedOn));
+    match sent(&actions).as_slice() {
+        [Message::Logon(logon)] => assert_eq!(logon.heart_bt_int.value, 45),
+        other => panic!("unexpected messages: {:?}", other),
+    }
+    assert_eq!(session.heart_bt_int(), Duration::from_secs(45));
+}
+
+#[test]
+fn test_acceptor_logon_reset_seq_num() {
+    let start = Instant::now();
+    let mut session = Session::new(config(SessionRole::Acceptor));
+    let mut broker = Broker::new();
+    session.on_connected(start);
+
+    let logon = MessageLogon::builder()
+        .with_session(&mut broker)
+        .encrypt_method(EncryptMethod::None)
+        .heart_bt_int(HeartBtInt::new(10))
+        .reset_seq_num_flag(ResetSeqNumFlag::Yes)
+        .build()
+        .unwrap();
+    let actions = session.on_message(Message::Logon(logon), start);
+    match sent(&actions).as_slice() {
+        [Message::Logon(logon)] => {
+            assert_eq!(logon.reset_seq_num_flag, Some(ResetSeqNumFlag::Yes));
+            assert_eq!(logon.header.msg_seq_num.value, 1);
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+}
+
+#[test]
+fn test_invalid_logon() {
+    let start = Instant::now();
+    let mut session = Session::new(config(SessionRole::Acceptor));
+    let mut broker = Broker::new();
+    session.on_connected(start);
+
+    let actions = session.on_message(broker.logon(-1), start);
+    assert!(matches!(sent(&actions).as_slice(), [Message::Logout(_)]));
+    assert_eq!(
+        actions.last(),
+        Some(&SessionAction::Disconnect(DisconnectReason::InvalidLogon(
+            "negative HeartBtInt".to_string()
+        )))
+    );
+    assert_eq!(session.state(), SessionState::Disconnected);
+}
+
+#[test]
+fn test_first_message_not_logon() {
+    let start = Instant::now();
+    let mut session = Session::new(config(SessionRole::Acceptor));
+    let mut broker = Broker::new();
+    session.on_connected(start);
+
+    let actions = session.on_message(broker.heartbeat(), start);
+    assert_eq!(
+        actions,
+        vec![SessionAction::Disconnect(DisconnectReason::NotLogon("0"))]
+    );
+    assert_eq!(session.state(), SessionState::Disconnected);
+}
+
+#[test]
+fn test_invalid_comp_id() {
+    let start = Instant::now();
+    let mut session = Session::new(config(SessionRole::Acceptor));
+    session.on_connected(start);
+
+    // Message sent by tested session to itself
+    let mut other = Session::new(config(SessionRole::Initiator));
+    let logon = match other.on_connected(start).pop() {
+        Some(SessionAction::Send(logon)) => *logon,
+        other => panic!("unexpected action: {:?}", other),
+    };
+
+    let actions = session.on_message(logon, start);
+    assert_eq!(
+        actions.last(),
+        Some(&SessionAction::Disconnect(
+            DisconnectReason::InvalidCompId {
+                expected_sender: "BROKER".to_string(),
+                expected_target: "CLIENT".to_string(),
+            }
+        ))
+    );
+}
+
+#[test]
+fn test_logon_timeout() {
+    let start = Instant::now();
+    let mut session = Session::new(config(SessionRole::Initiator));
+    session.on_connected(start);
+
+    assert_eq!(session.on_tick(secs(start, 9)), vec![]);
+    assert_eq!(
+        session.on_tick(secs(start, 10)),
+        vec![SessionAction::Disconnect(DisconnectReason::LogonTimeout)]
+    );
+}
+
+#[test]
+fn test_receive_application_message() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    let actions = session.on_message(broker.list_status_request(), start);
+    match actions.as_slice() {
+        [SessionAction::Receive(message)] => {
+            assert!(matches!(message.as_ref(), Message::ListStatusRequest(_)))
+        }
+        other => panic!("unexpected actions: {:?}", other),
+    }
+
+    assert_eq!(session.on_message(broker.heartbeat(), start), vec![]);
+    assert_eq!(session.next_target_msg_seq_num(), 4);
+}
+
+#[test]
+fn test_heartbeat() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    assert_eq!(session.on_tick(secs(start, 9)), vec![]);
+    session.on_message(broker.heartbeat(), secs(start, 9));
+
+    let actions = session.on_tick(secs(start, 10));
+    match sent(&actions).as_slice() {
+        [Message::Heartbeat(heartbeat)] => {
+            assert_eq!(heartbeat.test_req_id, None);
+            assert_eq!(heartbeat.header.msg_seq_num.value, 2);
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+    assert_eq!(session.on_tick(secs(start, 11)), vec![]);
+}
+
+#[test]
+fn test_answer_test_request() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    let actions = session.on_message(broker.test_request("PING"), start);
+    match sent(&actions).as_slice() {
+        [Message::Heartbeat(heartbeat)] => {
+            assert_eq!(
+                heartbeat.test_req_id,
+                Some(TestReqID::new("PING".to_string()))
+            );
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+}
+
+#[test]
+fn test_test_request_on_silence() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    // Heartbeat interval + transmission time
+    session.on_tick(secs(start, 10));
+    let actions = session.on_tick(secs(start, 12));
+    match sent(&actions).as_slice() {
+        [Message::TestRequest(test_request)] => {
+            assert_eq!(test_request.test_req_id.value, "1");
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+
+    // Counterparty answers
+    session.on_message(broker.heartbeat(), secs(start, 13));
+    assert!(session
+        .on_tick(secs(start, 20))
+        .iter()
+        .all(|action| matches!(
+            action,
+            SessionAction::Send(message) if matches!(message.as_ref(), Message::Heartbeat(_))
+        )));
+    assert!(session.is_logged_on());
+}
+
+#[test]
+fn test_heartbeat_timeout() {
+    let start = Instant::now();
+    let (mut session, _broker) = logged_on(start);
+
+    session.on_tick(secs(start, 12));
+    let actions = session.on_tick(secs(start, 24));
+    assert_eq!(
+        actions,
+        vec![
+            SessionAction::LoggedOut,
+            SessionAction::Disconnect(DisconnectReason::HeartbeatTimeout)
+        ]
+    );
+    assert_eq!(session.state(), SessionState::Disconnected);
+}
+
+#[test]
+fn test_logout() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    let actions = session.logout(Some("Bye".to_string()), start);
+    match sent(&actions).as_slice() {
+        [Message::Logout(logout)] => assert_eq!(logout.text, Some(Text::new("Bye".to_string()))),
+        other => panic!("unexpected messages: {:?}", other),
+    }
+    assert_eq!(session.state(), SessionState::LogoutSent);
+    assert_eq!(
+        session.send(Broker::new().list_status_request(), start),
+        Err(SessionError::NotLoggedOn)
+    );
+
+    let actions = session.on_message(broker.logout(), start);
+    assert_eq!(
+        actions,
+        vec![
+            SessionAction::LoggedOut,
+            SessionAction::Disconnect(DisconnectReason::Logout)
+        ]
+    );
+}
+
+#[test]
+fn test_logout_timeout() {
+    let start = Instant::now();
+    let (mut session, _broker) = logged_on(start);
+
+    session.logout(None, start);
+    assert_eq!(session.on_tick(secs(start, 9)), vec![]);
+    assert_eq!(
+        session.on_tick(secs(start, 10)),
+        vec![
+            SessionAction::LoggedOut,
+            SessionAction::Disconnect(DisconnectReason::LogoutTimeout)
+        ]
+    );
+}
+
+#[test]
+fn test_counterparty_logout() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    let actions = session.on_message(broker.logout(), start);
+    assert_eq!(actions.len(), 3);
+    assert!(matches!(sent(&actions).as_slice(), [Message::Logout(_)]));
+    assert_eq!(
+        actions[1..],
+        [
+            SessionAction::LoggedOut,
+            SessionAction::Disconnect(DisconnectReason::Logout)
+        ]
+    );
+}
+
+#[test]
+fn test_msg_seq_num_too_low() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    broker.next_seq_num = 1;
+    let actions = session.on_message(broker.heartbeat(), start);
+    assert_eq!(
+        actions.last(),
+        Some(&SessionAction::Disconnect(
+            DisconnectReason::MsgSeqNumTooLow {
+                expected: 2,
+                received: 1
+            }
+        ))
+    );
+}
+
+#[test]
+fn test_poss_dup_too_low_is_ignored() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    broker.next_seq_num = 1;
+    let mut heartbeat = broker.heartbeat();
+    heartbeat.header_mut().poss_dup_flag = Some(PossDupFlag::Yes);
+    assert_eq!(session.on_message(heartbeat, start), vec![]);
+    assert!(session.is_logged_on());
+}
+
+#[test]
+fn test_send() {
+    let start = Instant::now();
+    let mut session = Session::new(config(SessionRole::Initiator));
+    let mut broker = Broker::new();
+    assert_eq!(
+        session.send(Broker::new().list_status_request(), start),
+        Err(SessionError::NotLoggedOn)
+    );
+
+    session.on_connected(start);
+    session.on_message(broker.logon(10), start);
+
+    let request = Message::ListStatusRequest(
+        MessageListStatusRequest::builder()
+            .with_session(&mut session)
+            .list_id(ListID::new("LIST1".to_string()))
+            .build()
+            .unwrap(),
+    );
+    let actions = session.send(request, start).unwrap();
+    match sent(&actions).as_slice() {
+        [Message::ListStatusRequest(request)] => assert_eq!(request.header.msg_seq_num.value, 2),
+        other => panic!("unexpected messages: {:?}", other),
+    }
+    assert_eq!(session.next_sender_msg_seq_num(), 3);
+}
+
+#[test]
+fn test_disconnected() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    assert_eq!(session.on_disconnected(), vec![SessionAction::LoggedOut]);
+    assert_eq!(session.on_message(broker.heartbeat(), start), vec![]);
+    assert_eq!(session.on_disconnected(), vec![]);
+}
+
+#[test]
+fn test_logon_with_msg_seq_num_gap() {
+    let start = Instant::now();
+    let mut session = Session::new(config(SessionRole::Acceptor));
+    let mut broker = Broker { next_seq_num: 5 };
+    session.on_connected(start);
+
+    let actions = session.on_message(broker.logon(10), start);
+    assert_eq!(actions[1], SessionAction::LoggedOn);
+    match sent(&actions).as_slice() {
+        [Message::Logon(_), Message::ResendRequest(resend_request)] => {
+            assert_eq!(resend_request.begin_seq_no.value, 1);
+            assert_eq!(resend_request.end_seq_no.value, 0);
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+    assert_eq!(session.next_target_msg_seq_num(), 1);
+
+    // Counterparty fills every admin message, logon included
+    broker.next_seq_num = 1;
+    let gap_fill = poss_dup(broker.sequence_reset(true, 6));
+    assert_eq!(session.on_message(gap_fill, start), vec![]);
+    assert_eq!(session.next_target_msg_seq_num(), 6);
+
+    // Recovery is completed: next gap is requested again
+    broker.next_seq_num = 7;
+    let actions = session.on_message(broker.heartbeat(), start);
+    assert!(matches!(
+        sent(&actions).as_slice(),
+        [Message::ResendRequest(resend_request)] if resend_request.begin_seq_no.value == 6
+    ));
+}
+
+#[test]
+fn test_msg_seq_num_gap() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    // Messages 2 and 3 are lost
+    broker.next_seq_num = 4;
+    let actions = session.on_message(broker.list_status_request(), start);
+    match sent(&actions).as_slice() {
+        [Message::ResendRequest(resend_request)] => {
+            assert_eq!(resend_request.begin_seq_no.value, 2);
+            assert_eq!(resend_request.end_seq_no.value, 0);
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+    assert_eq!(received(&actions), vec![]);
+
+    // Resend is already requested
+    let actions = session.on_message(broker.list_status_request(), start);
+    assert_eq!(actions, vec![]);
+
+    // Counterparty resends its messages
+    broker.next_seq_num = 2;
+    let gap_fill = poss_dup(broker.sequence_reset(true, 3));
+    assert_eq!(session.on_message(gap_fill, start), vec![]);
+
+    let actions = session.on_message(poss_dup(broker.list_status_request()), start);
+    assert_eq!(received(&actions), vec![3, 4, 5]);
+    assert_eq!(session.next_target_msg_seq_num(), 6);
+
+    // Duplicates of queued messages are ignored
+    let actions = session.on_message(poss_dup(broker.list_status_request()), start);
+    assert_eq!(actions, vec![]);
+    assert!(session.is_logged_on());
+}
+
+#[test]
+fn test_msg_seq_num_gap_answers_resend_request() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    broker.next_seq_num = 3;
+    let actions = session.on_message(broker.resend_request(1, 0), start);
+    match sent(&actions).as_slice() {
+        [Message::ResendRequest(_), Message::SequenceReset(gap_fill)] => {
+            assert_eq!(gap_fill.header.msg_seq_num.value, 1);
+            assert_eq!(gap_fill.new_seq_no.value, 3);
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+}
+
+#[test]
+fn test_answer_resend_request() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    let mut original = Vec::new();
+    for list_id in &["LIST1", "LIST2"] {
+        let request = Message::ListStatusRequest(
+            MessageListStatusRequest::builder()
+                .with_session(&mut session)
+                .list_id(ListID::new(list_id.to_string()))
+                .build()
+                .unwrap(),
+        );
+        let mut actions = session.send(request, start).unwrap();
+        original.push(actions.pop().unwrap());
+
+        let actions = session.on_tick(secs(start, 10));
+        assert!(matches!(sent(&actions).as_slice(), [Message::Heartbeat(_)]));
+        session.on_message(broker.heartbeat(), secs(start, 10));
+    }
+    assert_eq!(session.next_sender_msg_seq_num(), 6);
+
+    let actions = session.on_message(broker.resend_request(2, 0), secs(start, 15));
+    match sent(&actions).as_slice() {
+        [Message::ListStatusRequest(first), Message::SequenceReset(gap_fill), Message::ListStatusRequest(second), Message::SequenceReset(last_gap_fill)] =>
+        {
+            for (message, original) in [first, second].iter().zip(original.iter()) {
+                let original = match original {
+                    SessionAction::Send(original) => original.header(),
+                    other => panic!("unexpected action: {:?}", other),
+                };
+                assert_eq!(message.header.msg_seq_num, original.msg_seq_num);
+                assert_eq!(message.header.poss_dup_flag, Some(PossDupFlag::Yes));
+                assert_eq!(
+                    message.header.orig_sending_time,
+                    Some(OrigSendingTime::new(original.sending_time.value))
+                );
+            }
+            assert_eq!(first.list_id.value, "LIST1");
+            assert_eq!(second.list_id.value, "LIST2");
+
+            assert_eq!(gap_fill.header.msg_seq_num.value, 3);
+            assert_eq!(gap_fill.header.poss_dup_flag, Some(PossDupFlag::Yes));
+            assert_eq!(gap_fill.gap_fill_flag, Some(GapFillFlag::Yes));
+            assert_eq!(gap_fill.new_seq_no.value, 4);
+
+            // Trailing heartbeat
+            assert_eq!(last_gap_fill.header.msg_seq_num.value, 5);
+            assert_eq!(last_gap_fill.new_seq_no.value, 6);
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+
+    // Resend does not consume sequence numbers
+    let actions = session.on_message(broker.resend_request(4, 5), secs(start, 15));
+    match sent(&actions).as_slice() {
+        [Message::ListStatusRequest(_), Message::SequenceReset(gap_fill)] => {
+            assert_eq!(gap_fill.header.msg_seq_num.value, 5);
+            assert_eq!(gap_fill.new_seq_no.value, 6);
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+    assert_eq!(session.next_sender_msg_seq_num(), 6);
+}
+
+#[test]
+fn test_sequence_reset() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    // Reset mode ignores MsgSeqNum
+    broker.next_seq_num = 1;
+    assert_eq!(
+        session.on_message(broker.sequence_reset(false, 10), start),
+        vec![]
+    );
+    assert_eq!(session.next_target_msg_seq_num(), 10);
+
+    let actions = session.on_message(broker.sequence_reset(false, 5), start);
+    match sent(&actions).as_slice() {
+        [Message::Reject(reject)] => {
+            assert_eq!(reject.ref_seq_num.value, 2);
+            assert_eq!(reject.ref_tag_id, Some(RefTagID::new(36)));
+            assert_eq!(reject.ref_msg_type, Some(RefMsgType::new("4".to_string())));
+            assert_eq!(
+                reject.session_reject_reason,
+                Some(SessionRejectReason::ValueIsIncorrect)
+            );
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+    assert_eq!(session.next_target_msg_seq_num(), 10);
+}
+
+#[test]
+fn test_gap_fill_new_seq_no_too_low() {
+    let start = Instant::now();
+    let (mut session, mut broker) = logged_on(start);
+
+    let actions = session.on_message(broker.sequence_reset(true, 1), start);
+    assert!(matches!(sent(&actions).as_slice(), [Message::Reject(_)]));
+    assert_eq!(session.next_target_msg_seq_num(), 3);
+}
+
+#[test]
+fn test_file_store_restart() -> io::Result<()> {
+    let start = Instant::now();
+    let dir = tempfile::tempdir()?;
+
+    let mut session =
+        Session::with_store(config(SessionRole::Initiator), FileStore::open(dir.path())?);
+    let mut broker = Broker::new();
+    session.on_connected(start);
+    session.on_message(broker.logon(10), start);
+    let request = Message::ListStatusRequest(
+        MessageListStatusRequest::builder()
+            .with_session(&mut session)
+            .list_id(ListID::new("LIST1".to_string()))
+            .build()
+            .unwrap(),
+    );
+    session.send(request, start).unwrap();
+    session.on_disconnected();
+    drop(session);
+
+    // Sequence numbers and sent messages survive restart
+    let mut session =
+        Session::with_store(config(SessionRole::Initiator), FileStore::open(dir.path())?);
+    assert_eq!(session.next_sender_msg_seq_num(), 3);
+    assert_eq!(session.next_target_msg_seq_num(), 2);
+
+    let actions = session.on_connected(start);
+    match sent(&actions).as_slice() {
+        [Message::Logon(logon)] => assert_eq!(logon.header.msg_seq_num.value, 3),
+        other => panic!("unexpected messages: {:?}", other),
+    }
+    session.on_message(broker.logon(10), start);
+
+    let actions = session.on_message(broker.resend_request(1, 0), start);
+    match sent(&actions).as_slice() {
+        [Message::SequenceReset(gap_fill), Message::ListStatusRequest(request), Message::SequenceReset(last_gap_fill)] =>
+        {
+            assert_eq!(gap_fill.new_seq_no.value, 2);
+            assert_eq!(request.header.msg_seq_num.value, 2);
+            assert_eq!(request.list_id.value, "LIST1");
+            assert_eq!(last_gap_fill.header.msg_seq_num.value, 3);
+            assert_eq!(last_gap_fill.new_seq_no.value, 4);
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+    Ok(())
+}
+
+/// Store whose disk is full
+struct FullStore(MemoryStore);
+
+impl MessageStore for FullStore {
+    fn set(&mut self, _msg_seq_num: u64, _data: &[u8]) -> io::Result<()> {
+        Err(io::Error::other("disk full"))
+    }
+
+    fn get_range(&self, begin: u64, end: u64) -> io::Result<Vec<(u64, Vec<u8>)>> {
+        self.0.get_range(begin, end)
+    }
+
+    fn next_sender_msg_seq_num(&self) -> u64 {
+        self.0.next_sender_msg_seq_num()
+    }
+
+    fn next_target_msg_seq_num(&self) -> u64 {
+        self.0.next_target_msg_seq_num()
+    }
+
+    fn set_next_sender_msg_seq_num(&mut self, value: u64) -> io::Result<()> {
+        self.0.set_next_sender_msg_seq_num(value)
+    }
+
+    fn set_next_target_msg_seq_num(&mut self, value: u64) -> io::Result<()> {
+        self.0.set_next_target_msg_seq_num(value)
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        self.0.reset()
+    }
+}
+
+#[test]
+fn test_store_failure() {
+    let start = Instant::now();
+    let mut session = Session::with_store(
+        config(SessionRole::Initiator),
+        FullStore(MemoryStore::new()),
+    );
+    let mut broker = Broker::new();
+    session.on_connected(start);
+    session.on_message(broker.logon(10), start);
+
+    let actions = session
+        .send(Broker::new().list_status_request(), start)
+        .unwrap();
+    assert_eq!(
+        actions[1..],
+        [
+            SessionAction::LoggedOut,
+            SessionAction::Disconnect(DisconnectReason::StoreFailure("disk full".to_string()))
+        ]
+    );
+    assert_eq!(session.state(), SessionState::Disconnected);
+}
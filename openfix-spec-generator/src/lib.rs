@@ -1,44 +1,118 @@
+use anyhow::Context;
 use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
-use std::io::{BufReader, BufWriter, Write};
+use std::io::BufWriter;
 use std::path::Path;
-use std::process::Command;
+use thiserror::Error;
+
+pub mod orchestra;
 
 // =====================================
 // Define common code block;
 
-static CODE_HEADER: &str = "
-#[allow(unused_imports)]
-use chrono::prelude::*;
+fn code_header(options: &GeneratorOptions) -> anyhow::Result<TokenStream> {
+    let imports = match &options.runtime_crate {
+        Some(runtime_crate) => {
+            let runtime_crate: syn::Path = syn::parse_str(runtime_crate)
+                .with_context(|| format!("invalid runtime crate path: {}", runtime_crate))?;
+            quote! {
+                #[allow(unused_imports)]
+                use #runtime_crate::chrono::prelude::*;
+
+                #[allow(unused_imports)]
+                use #runtime_crate::prelude::*;
+            }
+        }
+        None => quote! {
+            #[allow(unused_imports)]
+            use chrono::prelude::*;
+
+            #[allow(unused_imports)]
+            use crate::prelude::*;
+        },
+    };
+
+    Ok(quote! {
+        #imports
+
+        #[allow(unused_imports)]
+        use std::{fmt, io::{self, Write}, ops::Range};
+    })
+}
+
+/// Build identifier from name, escaping keywords (like "yield" for example)
+fn ident(name: &str) -> Ident {
+    match syn::parse_str::<Ident>(name) {
+        Ok(ident) => ident,
+        Err(_) => Ident::new_raw(name, Span::call_site()),
+    }
+}
+
+/// Field ID / count literal, without type suffix
+fn number(value: u32) -> Literal {
+    Literal::u32_unsuffixed(value)
+}
+
+/// Doc comment attributes from markdown text
+fn doc_attrs(text: &str) -> TokenStream {
+    let lines = text.lines().map(|line| {
+        if line.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", line)
+        }
+    });
+    quote!(#(#[doc = #lines])*)
+}
+
+/// Doc comment of a message / component: title, dictionary documentation and required fields
+fn refs_doc(title: String, doc: Option<&str>, refs: &[Reference]) -> TokenStream {
+    let mut paragraphs = vec![title];
+    paragraphs.extend(doc.map(String::from));
+
+    let required: Vec<_> = refs
+        .iter()
+        .filter(|x| x.is_required())
+        .map(|x| x.name())
+        .collect();
+    if !required.is_empty() {
+        paragraphs.push(format!("Required fields: {}", required.join(", ")));
+    }
+
+    doc_attrs(&paragraphs.join("\n\n"))
+}
 
-";
+/// Check generated code is valid Rust and format it
+fn format_code(tokens: TokenStream) -> anyhow::Result<String> {
+    let file = syn::parse2::<syn::File>(tokens).context("generated code is not valid Rust")?;
+    Ok(prettyplease::unparse(&file))
+}
 
 // =====================================
 // Basic types
 
 /// Basic wrapper to convert FIX required field to boolean
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, PartialEq)]
 pub enum Required {
     Y,
+    #[default]
     N,
 }
 
-impl Into<bool> for Required {
-    fn into(self) -> bool {
-        match self {
-            Self::Y => true,
-            Self::N => false,
+impl From<Required> for bool {
+    fn from(value: Required) -> Self {
+        match value {
+            Required::Y => true,
+            Required::N => false,
         }
     }
 }
 
-impl Default for Required {
-    fn default() -> Self {
-        Self::N
-    }
-}
-
 /// Allowed message receiver category
 #[derive(Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -47,8 +121,19 @@ pub enum MessageCategory {
     App,
 }
 
+/// How an overlay dictionary item is applied to base dictionary item with the same name
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlayMode {
+    /// Add new references / values to existing item
+    #[default]
+    Extend,
+    /// Drop existing item and use overlay one
+    Replace,
+}
+
 /// Know type (so much types defined in FIX protocol)
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum FieldType {
     Boolean,
@@ -75,6 +160,8 @@ pub enum FieldType {
     Quantity,
     Currency,
     MultipleValueString,
+    MultipleCharValue,
+    MultipleStringValue,
     Exchange,
     #[serde(rename = "LOCALMKTDATE")]
     LocalMarketDate,
@@ -84,96 +171,123 @@ pub enum FieldType {
 }
 
 impl FieldType {
-    fn as_rust_type(&self) -> &'static str {
+    /// Types which can be generated as enum
+    fn supports_enum_values(&self) -> bool {
+        matches!(
+            self,
+            Self::String
+                | Self::Char
+                | Self::Int
+                | Self::MultipleValueString
+                | Self::MultipleCharValue
+                | Self::MultipleStringValue
+                | Self::Boolean
+                | Self::NumInGroup
+        )
+    }
+
+    /// Space separated list of values
+    fn is_multiple_value(&self) -> bool {
+        matches!(
+            self,
+            Self::MultipleValueString | Self::MultipleCharValue | Self::MultipleStringValue
+        )
+    }
+
+    /// Type name used in dictionaries (ie: "UTCTIMESTAMP")
+    fn as_fix_name(&self) -> String {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(name)) => name,
+            _ => format!("{:?}", self),
+        }
+    }
+
+    fn as_rust_type(&self) -> TokenStream {
         // Some type may be improved
         match *self {
-            Self::Boolean => "bool",
-            Self::Char => "char",
-            Self::Int => "i32",
-            Self::Float => "f64",
-            Self::String => "String",
-            Self::Seqnum => "usize",
-            Self::Length => "usize",
-            Self::UtcTimestamp => "f64",
-            Self::MonthYear => "String",
-            Self::DayOfMonth => "u8",
-            Self::UtcDate => "chrono::NaiveDate",
-            Self::UtcDateOnly => "String",
-            Self::Date => "String",
-            Self::UtcTimeOnly => "String",
-            Self::Time => "chrono::NaiveTime",
-            Self::Data => "String",
-            Self::NumInGroup => "String",
-            Self::Price => "f64",
-            Self::Amount => "f64",
-            Self::Quantity => "f64",
-            Self::Currency => "String",
-            Self::MultipleValueString => "String",
-            Self::Exchange => "String",
-            Self::LocalMarketDate => "String",
-            Self::PriceOffset => "f64",
-            Self::Percentage => "f64",
-            Self::Country => "String",
+            Self::Boolean => quote!(bool),
+            Self::Char => quote!(char),
+            Self::Int => quote!(i32),
+            Self::Float => quote!(f64),
+            Self::String => quote!(String),
+            Self::Seqnum => quote!(u64),
+            Self::Length => quote!(u64),
+            Self::UtcTimestamp => quote!(DateTime<Utc>),
+            Self::MonthYear => quote!(MonthYear),
+            Self::DayOfMonth => quote!(u8),
+            Self::UtcDate => quote!(NaiveDate),
+            Self::UtcDateOnly => quote!(NaiveDate),
+            Self::Date => quote!(NaiveDate),
+            Self::UtcTimeOnly => quote!(NaiveTime),
+            Self::Time => quote!(DateTime<Utc>),
+            Self::Data => quote!(Vec<u8>),
+            Self::NumInGroup => quote!(u32),
+            Self::Price => quote!(FixDecimal),
+            Self::Amount => quote!(FixDecimal),
+            Self::Quantity => quote!(FixDecimal),
+            Self::Currency => quote!(String),
+            Self::MultipleValueString => quote!(Vec<String>),
+            Self::MultipleCharValue => quote!(Vec<char>),
+            Self::MultipleStringValue => quote!(Vec<String>),
+            Self::Exchange => quote!(String),
+            Self::LocalMarketDate => quote!(NaiveDate),
+            Self::PriceOffset => quote!(FixDecimal),
+            Self::Percentage => quote!(FixDecimal),
+            Self::Country => quote!(String),
         }
     }
 }
 
 // =====================================
-/// Reference to FieldDef
-
-macro_rules! sanitize_field_name {
-    // Check if field is not a keyword (like "yield" for example)
-    ($x:expr) => {
-        match syn::parse_str::<syn::Ident>(&$x) {
-            Err(_) => format!("r#{}", $x),
-            Ok(_) => $x.into(),
-        }
-    };
-}
+// Reference to FieldDef
 
-fn format_optional_struct_field(required: &Required, field_name: &str, type_name: &str) -> String {
+fn optional_struct_field(
+    required: &Required,
+    field_name: &Ident,
+    type_name: &Ident,
+) -> TokenStream {
     match required {
-        Required::Y => format!("pub {}: {}", field_name, type_name),
-        Required::N => format!("pub {}: Option<{}>", field_name, type_name),
+        Required::Y => quote!(pub #field_name: #type_name),
+        Required::N => quote!(pub #field_name: Option<#type_name>),
     }
 }
 
-fn format_optional_function_call(required: &Required, field_name: &str, call_name: &str) -> String {
-    let field_name = sanitize_field_name!(field_name);
+fn optional_encode_call(required: &Required, field_name: &Ident) -> TokenStream {
     match required {
-        Required::Y => format!("self.{}.{};", field_name, call_name),
-        Required::N => format!(
-            "if let Some(v) = &self.{0} {{ v.{1}; }}",
-            field_name, call_name
-        ),
+        Required::Y => quote!(self.#field_name.encode_message(writer)?;),
+        Required::N => quote! {
+            if let Some(v) = &self.#field_name {
+                v.encode_message(writer)?;
+            }
+        },
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct FieldRef {
     name: String,
     required: Required,
 }
 
 impl FieldRef {
-    fn as_field_name(&self) -> String {
-        sanitize_field_name!(self.name.to_case(Case::Snake))
+    fn as_field_name(&self) -> Ident {
+        ident(&self.name.to_case(Case::Snake))
     }
 
-    fn as_class_name<'a>(&'a self) -> &'a str {
-        &self.name
+    fn as_class_name(&self) -> Ident {
+        ident(&self.name)
     }
 
-    fn as_struct_field_item(&self) -> String {
-        format_optional_struct_field(&self.required, &self.as_field_name(), self.as_class_name())
+    fn as_struct_field_item(&self) -> TokenStream {
+        optional_struct_field(&self.required, &self.as_field_name(), &self.as_class_name())
     }
 
-    fn as_function_call(&self, call_name: &str) -> String {
-        format_optional_function_call(&self.required, &self.name.to_case(Case::Snake), call_name)
+    fn as_encode_call(&self) -> TokenStream {
+        optional_encode_call(&self.required, &self.as_field_name())
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct ComponentRef {
     name: String,
     #[serde(default)]
@@ -181,24 +295,24 @@ pub struct ComponentRef {
 }
 
 impl ComponentRef {
-    fn as_field_name(&self) -> String {
-        sanitize_field_name!(self.name.to_case(Case::Snake))
+    fn as_field_name(&self) -> Ident {
+        ident(&self.name.to_case(Case::Snake))
     }
 
-    fn as_class_name<'a>(&'a self) -> &'a str {
-        &self.name
+    fn as_class_name(&self) -> Ident {
+        ident(&self.name)
     }
 
-    fn as_struct_field_item(&self) -> String {
-        format_optional_struct_field(&self.required, &self.as_field_name(), self.as_class_name())
+    fn as_struct_field_item(&self) -> TokenStream {
+        optional_struct_field(&self.required, &self.as_field_name(), &self.as_class_name())
     }
 
-    fn as_function_call(&self, call_name: &str) -> String {
-        format_optional_function_call(&self.required, &self.name.to_case(Case::Snake), call_name)
+    fn as_encode_call(&self) -> TokenStream {
+        optional_encode_call(&self.required, &self.as_field_name())
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct GroupRef {
     name: String,
     required: Required,
@@ -212,90 +326,70 @@ impl GroupRef {
         format!("{}{}", cls_prefix, self.name.to_case(Case::UpperCamel))
     }
 
-    fn as_group_struct(&self, cls_prefix: &str) -> String {
-        let group_elements: Vec<_> = self
-            .refs
-            .iter()
-            .map(|x| format!("\t{0}({0}),", x.as_type_value()))
-            .collect();
-
-        let group_encode: Vec<_> = self
-            .refs
-            .iter()
-            .map(|x| {
-                format!(
-                    "\t\t\tSelf::{}(ref x) => x.encode_message(writer),",
-                    x.as_type_value()
-                )
-            })
-            .collect();
+    fn as_group_struct(&self, cls_prefix: &str, ctx: &SpecContext) -> TokenStream {
+        let cls_name = self.cls_name(cls_prefix);
+        let doc = refs_doc(
+            format!("Entry of {} repeating group", self.name),
+            None,
+            &self.refs,
+        );
+        let entry_code = component_as_code(&cls_name, &self.name, doc, &self.refs, true, ctx);
+        let cls_name = ident(&cls_name);
+        let delimiter = ctx.first_tag(&self.refs);
+
+        quote! {
+            #entry_code
+
+            impl FixGroupEntry for #cls_name {
+                const DELIMITER: u32 = #delimiter::FIX_KEY;
+                const DICTIONARY_TAGS: &'static [u32] = FIELD_TAGS;
+            }
+        }
+    }
 
-        let group_decode: Vec<_> = self
-            .refs
-            .iter()
-            .map(|x| format!("\t\ttry_decode!({});", x.as_type_value()))
-            .collect();
+    fn as_field_name(&self) -> Ident {
+        ident(&self.name.to_case(Case::Snake))
+    }
 
-        format!(
-            "
-#[derive(Debug, PartialEq)]
-pub enum {cls_name} {{
-{group_elements}
-}}
+    fn as_class_name(&self) -> Ident {
+        ident(&self.name)
+    }
 
-impl AsFixMessage for {cls_name} {{
-    fn encode_message<W>(&self, writer: &mut W) -> io::Result<()>
-    where
-        W: Write
-    {{
-        match *self {{
-{group_encode}
-        }}
-    }}
-}}
-
-impl FromFixMessage for {cls_name} {{
-    fn decode_message(items: &FixFieldItems) -> Result<Self, FixParseError> {{
-        // This may be improved ...
-        macro_rules! try_decode {{
-            ($t:tt) => {{
-                if let Ok(result) = $t::decode_message(items) {{
-                    return Ok(Self::$t(result));
-                }}
-            }};
-        }}
-
-{group_decode}
-
-        Err(FixParseError::InvalidData)
-    }}
-}}
-
-",
-            cls_name = self.cls_name(cls_prefix),
-            group_elements = group_elements.join("\n"),
-            group_encode = group_encode.join("\n"),
-            group_decode = group_decode.join("\n"),
-        )
+    fn as_struct_field_item(&self, cls_prefix: &str) -> TokenStream {
+        let field_name = self.as_field_name();
+        let cls_name = ident(&self.cls_name(cls_prefix));
+        quote!(pub #field_name: Vec<#cls_name>)
     }
 
-    fn as_field_name(&self) -> String {
-        sanitize_field_name!(self.name.to_case(Case::Snake))
+    fn as_encode_call(&self) -> TokenStream {
+        let field_name = self.as_field_name();
+        let class_name = self.as_class_name();
+        quote!(encode_group(writer, #class_name::FIX_KEY, &self.#field_name)?;)
     }
-    fn as_struct_field_item(&self, cls_prefix: &str) -> String {
-        format_optional_struct_field(
-            &self.required,
-            &self.as_field_name(),
-            &self.cls_name(cls_prefix),
-        )
+
+    fn as_group_range_call(&self, cls_prefix: &str) -> TokenStream {
+        let class_name = self.as_class_name();
+        let cls_name = ident(&self.cls_name(cls_prefix));
+        quote! {
+            ranges.extend(group_range::<#cls_name>(items, #class_name::FIX_KEY, #class_name::FIX_NAME));
+        }
     }
 
-    fn as_function_call(&self, call_name: &str, _cls_prefix: &str) -> String {
-        format_optional_function_call(&self.required, &self.name.to_case(Case::Snake), call_name)
+    fn as_decode_call(&self) -> TokenStream {
+        let class_name = self.as_class_name();
+        match self.required {
+            Required::Y => quote! {
+                decode_group(items, #class_name::FIX_KEY, #class_name::FIX_NAME)?
+                    .ok_or_else(|| FixParseError::MissingRequiredField(#class_name::error_context(None)))?
+            },
+            Required::N => quote! {
+                decode_group(items, #class_name::FIX_KEY, #class_name::FIX_NAME)?.unwrap_or_default()
+            },
+        }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub enum Reference {
     #[serde(rename = "field")]
     FieldRef(FieldRef),
@@ -308,7 +402,7 @@ pub enum Reference {
 }
 
 impl Reference {
-    fn as_type_value<'a>(&'a self) -> &'a str {
+    fn name(&self) -> &str {
         match self {
             Self::FieldRef(x) => &x.name,
             Self::ComponentRef(x) => &x.name,
@@ -316,23 +410,15 @@ impl Reference {
         }
     }
 
-    fn is_required<'a>(&'a self) -> &'a Required {
-        match self {
-            Self::FieldRef(x) => &x.required,
-            Self::ComponentRef(x) => &x.required,
-            Self::GroupRef(x) => &x.required,
-        }
-    }
-
-    fn as_group_struct(&self, cls_prefix: &str) -> Option<String> {
+    fn as_group_struct(&self, cls_prefix: &str, ctx: &SpecContext) -> Option<TokenStream> {
         match self {
             Self::FieldRef(_) => None,
             Self::ComponentRef(_) => None,
-            Self::GroupRef(x) => Some(x.as_group_struct(cls_prefix)),
+            Self::GroupRef(x) => Some(x.as_group_struct(cls_prefix, ctx)),
         }
     }
 
-    fn as_field_name(&self) -> String {
+    fn as_field_name(&self) -> Ident {
         match self {
             Self::FieldRef(x) => x.as_field_name(),
             Self::ComponentRef(x) => x.as_field_name(),
@@ -340,15 +426,32 @@ impl Reference {
         }
     }
 
-    fn as_class_name(&self, cls_prefix: &str) -> String {
+    /// Code adding items of repeating groups found in this reference to `ranges`
+    fn as_group_range_call(&self, cls_prefix: &str) -> Option<TokenStream> {
         match self {
-            Self::FieldRef(x) => x.as_class_name().to_string(),
-            Self::ComponentRef(x) => x.as_class_name().to_string(),
-            Self::GroupRef(x) => x.cls_name(cls_prefix),
+            Self::FieldRef(_) => None,
+            Self::ComponentRef(x) => {
+                let class_name = x.as_class_name();
+                Some(quote!(ranges.extend(#class_name::group_ranges(items));))
+            }
+            Self::GroupRef(x) => Some(x.as_group_range_call(cls_prefix)),
         }
     }
 
-    fn as_struct_field_item(&self, cls_prefix: &str) -> String {
+    fn as_decode_call(&self) -> TokenStream {
+        let (class_name, required) = match self {
+            Self::FieldRef(x) => (x.as_class_name(), x.required),
+            Self::ComponentRef(x) => (x.as_class_name(), x.required),
+            Self::GroupRef(x) => return x.as_decode_call(),
+        };
+
+        match required {
+            Required::Y => quote!(#class_name::decode_items(items)?),
+            Required::N => quote!(#class_name::decode_optional_items(items)?),
+        }
+    }
+
+    fn as_struct_field_item(&self, cls_prefix: &str) -> TokenStream {
         match self {
             Self::FieldRef(x) => x.as_struct_field_item(),
             Self::ComponentRef(x) => x.as_struct_field_item(),
@@ -356,11 +459,213 @@ impl Reference {
         }
     }
 
-    fn as_function_call(&self, cls_prefix: &str, call_name: &str) -> String {
+    fn is_required(&self) -> bool {
+        match self {
+            Self::FieldRef(x) => x.required.into(),
+            Self::ComponentRef(x) => x.required.into(),
+            Self::GroupRef(x) => x.required.into(),
+        }
+    }
+
+    fn as_value_type(&self, cls_prefix: &str) -> TokenStream {
+        match self {
+            Self::FieldRef(x) => x.as_class_name().into_token_stream(),
+            Self::ComponentRef(x) => x.as_class_name().into_token_stream(),
+            Self::GroupRef(x) => {
+                let cls_name = ident(&x.cls_name(cls_prefix));
+                quote!(Vec<#cls_name>)
+            }
+        }
+    }
+
+    /// Name used to report this reference as missing
+    fn as_missing_name(&self) -> TokenStream {
+        match self {
+            Self::FieldRef(x) => {
+                let class_name = x.as_class_name();
+                quote!(#class_name::FIX_NAME)
+            }
+            Self::ComponentRef(x) => {
+                let name = &x.name;
+                quote!(#name)
+            }
+            Self::GroupRef(x) => {
+                let class_name = x.as_class_name();
+                quote!(#class_name::FIX_NAME)
+            }
+        }
+    }
+
+    fn as_builder_field(&self, cls_prefix: &str) -> TokenStream {
+        let field_name = self.as_field_name();
+        let value_type = self.as_value_type(cls_prefix);
+        match self {
+            Self::GroupRef(_) => quote!(#field_name: #value_type),
+            _ => quote!(#field_name: Option<#value_type>),
+        }
+    }
+
+    fn as_builder_setter(&self, cls_prefix: &str) -> TokenStream {
+        let field_name = self.as_field_name();
+        let value_type = self.as_value_type(cls_prefix);
+        let value = match self {
+            Self::GroupRef(_) => quote!(value),
+            _ => quote!(Some(value)),
+        };
+
+        quote! {
+            pub fn #field_name(mut self, value: #value_type) -> Self {
+                self.#field_name = #value;
+                self
+            }
+        }
+    }
+
+    fn as_builder_check(&self) -> Option<TokenStream> {
+        if !self.is_required() {
+            return None;
+        }
+
+        let field_name = self.as_field_name();
+        let missing_name = self.as_missing_name();
+        let condition = match self {
+            Self::GroupRef(_) => quote!(is_empty),
+            _ => quote!(is_none),
+        };
+
+        Some(quote! {
+            if self.#field_name.#condition() {
+                missing.push(#missing_name);
+            }
+        })
+    }
+
+    fn as_builder_assign(&self) -> TokenStream {
+        let field_name = self.as_field_name();
+        match self {
+            Self::GroupRef(_) => quote!(#field_name: self.#field_name,),
+            _ if self.is_required() => quote!(#field_name: self.#field_name.unwrap(),),
+            _ => quote!(#field_name: self.#field_name,),
+        }
+    }
+
+    fn as_builder_from(&self) -> TokenStream {
+        let field_name = self.as_field_name();
+        match self {
+            Self::GroupRef(_) => quote!(#field_name: value.#field_name,),
+            _ if self.is_required() => quote!(#field_name: Some(value.#field_name),),
+            _ => quote!(#field_name: value.#field_name,),
+        }
+    }
+
+    fn as_encode_call(&self) -> TokenStream {
         match self {
-            Self::FieldRef(x) => x.as_function_call(call_name),
-            Self::ComponentRef(x) => x.as_function_call(call_name),
-            Self::GroupRef(x) => x.as_function_call(call_name, cls_prefix),
+            Self::FieldRef(x) => x.as_encode_call(),
+            Self::ComponentRef(x) => x.as_encode_call(),
+            Self::GroupRef(x) => x.as_encode_call(),
+        }
+    }
+}
+
+// =====================================
+// Reference resolution
+
+/// Lookup tables used to resolve references while generating code
+struct SpecContext<'a> {
+    components: HashMap<&'a str, &'a Component>,
+}
+
+impl<'a> SpecContext<'a> {
+    fn new(spec: &'a FixSpec) -> Self {
+        let components = spec
+            .component
+            .iter()
+            .flat_map(|x| x.items.iter())
+            .map(|x| (x.name.as_str(), x))
+            .collect();
+
+        Self { components }
+    }
+
+    fn component_refs(&self, name: &str) -> &'a [Reference] {
+        match self.components.get(name) {
+            Some(component) => &component.refs,
+            // Dictionary is validated before generating code
+            None => unreachable!("unknown component reference: {}", name),
+        }
+    }
+
+    /// List every field that may appear in references (nested ones included).
+    ///
+    /// IDs are generated from field constants, so generated code does not depend on field numbers.
+    fn collect_tags(&self, refs: &[Reference], tags: &mut Vec<Ident>) {
+        for item in refs {
+            match item {
+                Reference::FieldRef(x) => tags.push(x.as_class_name()),
+                Reference::ComponentRef(x) => self.collect_tags(self.component_refs(&x.name), tags),
+                Reference::GroupRef(x) => {
+                    tags.push(x.as_class_name());
+                    self.collect_tags(&x.refs, tags);
+                }
+            }
+        }
+    }
+
+    /// List names of every field that may appear in references (nested ones included)
+    fn collect_field_names(&self, refs: &'a [Reference], names: &mut Vec<&'a str>) {
+        for item in refs {
+            match item {
+                Reference::FieldRef(x) => names.push(&x.name),
+                Reference::ComponentRef(x) => {
+                    self.collect_field_names(self.component_refs(&x.name), names)
+                }
+                Reference::GroupRef(x) => {
+                    names.push(&x.name);
+                    self.collect_field_names(&x.refs, names);
+                }
+            }
+        }
+    }
+
+    /// Header, trailer and messages using each field
+    fn field_users(&self, spec: &'a FixSpec) -> HashMap<&'a str, Vec<&'a str>> {
+        let users = spec
+            .header
+            .iter()
+            .map(|x| ("StandardHeader", x.refs.as_slice()))
+            .chain(
+                spec.trailer
+                    .iter()
+                    .map(|x| ("StandardTrailer", x.refs.as_slice())),
+            )
+            .chain(
+                spec.message
+                    .items
+                    .iter()
+                    .map(|x| (x.name.as_str(), x.refs.as_slice())),
+            );
+
+        let mut output: HashMap<_, Vec<_>> = HashMap::new();
+        for (user, refs) in users {
+            let mut names = Vec::new();
+            self.collect_field_names(refs, &mut names);
+            for name in names {
+                let field_users = output.entry(name).or_default();
+                if !field_users.contains(&user) {
+                    field_users.push(user);
+                }
+            }
+        }
+        output
+    }
+
+    /// Get first field of references, which is the group delimiter
+    fn first_tag(&self, refs: &[Reference]) -> Ident {
+        match refs.first() {
+            Some(Reference::FieldRef(x)) => x.as_class_name(),
+            Some(Reference::ComponentRef(x)) => self.first_tag(self.component_refs(&x.name)),
+            Some(Reference::GroupRef(x)) => x.as_class_name(),
+            None => panic!("cannot find delimiter of empty group"),
         }
     }
 }
@@ -370,47 +675,59 @@ impl Reference {
 
 #[derive(Debug)]
 struct RefGeneratedCode {
-    classes: Vec<String>,
-    fields: Vec<String>,
-    fields_encode: Vec<String>,
-    fields_decode: Vec<String>,
+    classes: Vec<TokenStream>,
+    fields: Vec<TokenStream>,
+    fields_encode: Vec<TokenStream>,
+    fields_decode: Vec<TokenStream>,
+    builder_fields: Vec<TokenStream>,
+    builder_setters: Vec<TokenStream>,
+    builder_checks: Vec<TokenStream>,
+    builder_assigns: Vec<TokenStream>,
+    builder_from: Vec<TokenStream>,
+    group_ranges: Vec<TokenStream>,
 }
 
-fn generate_ref_code(refs: &Vec<Reference>, cls_prefix: &str) -> RefGeneratedCode {
+fn generate_ref_code(refs: &[Reference], cls_prefix: &str, ctx: &SpecContext) -> RefGeneratedCode {
     let classes = refs
         .iter()
-        .filter_map(|x| x.as_group_struct(cls_prefix))
+        .filter_map(|x| x.as_group_struct(cls_prefix, ctx))
         .collect();
 
     let fields = refs
         .iter()
-        .map(|x| format!("\t{},", x.as_struct_field_item(cls_prefix)))
+        .map(|x| x.as_struct_field_item(cls_prefix))
         .collect();
 
-    let fields_encode = refs
+    let fields_encode = refs.iter().map(|x| x.as_encode_call()).collect();
+
+    let fields_decode = refs
         .iter()
         .map(|x| {
-            format!(
-                "\t\t{}",
-                x.as_function_call(cls_prefix, "encode_message(writer)?")
-            )
+            let field_name = x.as_field_name();
+            let decode_call = x.as_decode_call();
+            quote!(#field_name: #decode_call,)
         })
         .collect();
 
-    let fields_decode = refs
+    let builder_fields = refs
         .iter()
-        .map(|x| match x.is_required() {
-            Required::Y => format!(
-                "\t\t\t{}: {}::decode_message(items)?,",
-                x.as_field_name(),
-                x.as_class_name(cls_prefix),
-            ),
-            Required::N => format!(
-                "\t\t\t{}: {}::decode_message(items).ok(),",
-                x.as_field_name(),
-                x.as_class_name(cls_prefix),
-            ),
-        })
+        .map(|x| x.as_builder_field(cls_prefix))
+        .collect();
+
+    let builder_setters = refs
+        .iter()
+        .map(|x| x.as_builder_setter(cls_prefix))
+        .collect();
+
+    let builder_checks = refs.iter().filter_map(|x| x.as_builder_check()).collect();
+
+    let builder_assigns = refs.iter().map(|x| x.as_builder_assign()).collect();
+
+    let builder_from = refs.iter().map(|x| x.as_builder_from()).collect();
+
+    let group_ranges = refs
+        .iter()
+        .filter_map(|x| x.as_group_range_call(cls_prefix))
         .collect();
 
     RefGeneratedCode {
@@ -418,85 +735,304 @@ fn generate_ref_code(refs: &Vec<Reference>, cls_prefix: &str) -> RefGeneratedCod
         fields,
         fields_encode,
         fields_decode,
+        builder_fields,
+        builder_setters,
+        builder_checks,
+        builder_assigns,
+        builder_from,
+        group_ranges,
     }
 }
 
-fn spec_as_code(cls_name: &str, refs: &Vec<Reference>) -> String {
-    let gen = generate_ref_code(refs, cls_name);
+/// Generate component code (used by header, trailer, components and group entries).
+///
+/// Group entries also keep fields missing from dictionary found in their items.
+fn component_as_code(
+    cls_name: &str,
+    fix_name: &str,
+    doc: TokenStream,
+    refs: &[Reference],
+    extra_fields: bool,
+    ctx: &SpecContext,
+) -> TokenStream {
+    let RefGeneratedCode {
+        classes,
+        fields,
+        fields_encode,
+        fields_decode,
+        builder_fields,
+        builder_setters,
+        builder_checks,
+        builder_assigns,
+        builder_from,
+        group_ranges,
+    } = generate_ref_code(refs, cls_name, ctx);
+    let builder_name = format_ident!("{}Builder", cls_name);
+    let cls_name = ident(cls_name);
+    let tags = tags_as_code(refs, ctx);
+    let group_ranges = if group_ranges.is_empty() {
+        quote!(Vec::new())
+    } else {
+        quote! {
+            let mut ranges = Vec::new();
+            #(#group_ranges)*
+            ranges
+        }
+    };
+
+    let extra = extra_fields.then(|| ExtraFieldsCode {
+        field: quote! {
+            /// Fields not defined in dictionary, in wire order
+            pub extra_fields: Vec<FixExtraField>,
+        },
+        encode: quote!(encode_extra_fields(writer, &self.extra_fields)?;),
+        decode: quote! {
+            extra_fields: decode_extra_fields(items, &[Self::FIX_TAGS], &Self::group_ranges(items)),
+        },
+        builder_field: quote!(extra_fields: Vec<FixExtraField>,),
+        builder_setter: quote! {
+            pub fn extra_fields(mut self, value: Vec<FixExtraField>) -> Self {
+                self.extra_fields = value;
+                self
+            }
+        },
+        builder_assign: quote!(extra_fields: self.extra_fields,),
+        builder_from: quote!(extra_fields: value.extra_fields,),
+    });
+    let ExtraFieldsCode {
+        field: extra_field,
+        encode: extra_encode,
+        decode: extra_decode,
+        builder_field: extra_builder_field,
+        builder_setter: extra_builder_setter,
+        builder_assign: extra_builder_assign,
+        builder_from: extra_builder_from,
+    } = extra.unwrap_or_default();
+
+    quote! {
+        #(#classes)*
+
+        #doc
+        #[derive(Debug, PartialEq)]
+        pub struct #cls_name {
+            #(#fields,)*
+            #extra_field
+        }
 
-    format!(
-        "
-{classes}
+        impl AsFixMessage for #cls_name {
+            #[allow(unused_variables)]
+            fn encode_message<W>(&self, writer: &mut W) -> io::Result<()>
+            where
+                W: Write
+            {
+                #(#fields_encode)*
+                #extra_encode
 
-#[derive(Debug, PartialEq)]
-pub struct {cls_name} {{
-{fields}
-}}
+                Ok(())
+            }
+        }
 
-impl AsFixMessage for {cls_name} {{
-    #[allow(unused_variables)]
-    fn encode_message<W>(&self, writer: &mut W) -> io::Result<()>
-    where
-        W: Write
-    {{
-{fields_encode}
+        impl #cls_name {
+            #[allow(unused_variables)]
+            fn decode_fields(items: &[FixFieldItem]) -> Result<Self, FixParseError> {
+                Ok(Self {
+                    #(#fields_decode)*
+                    #extra_decode
+                })
+            }
+        }
 
-        Ok(())
-    }}
-}}
-
-impl FromFixMessage for {cls_name} {{
-    #[allow(unused_variables)]
-    fn decode_message(items: &FixFieldItems) -> Result<Self, FixParseError> {{
-        Ok(Self {{
-{fields_decode}
-        }})
-    }}
-}}
-
-",
-        cls_name = cls_name,
-        fields = gen.fields.join("\n"),
-        classes = gen.classes.join("\n"),
-        fields_encode = gen.fields_encode.join("\n"),
-        fields_decode = gen.fields_decode.join("\n"),
-    )
+        impl FromFixMessage for #cls_name {
+            fn decode_items(items: &[FixFieldItem]) -> Result<Self, FixParseError> {
+                Self::decode_fields(items).map_err(|e| e.with_parent(#fix_name))
+            }
+        }
+
+        impl FixComponent for #cls_name {
+            const FIX_TAGS: &'static [u32] = &[#tags];
+
+            #[allow(unused_variables)]
+            fn group_ranges(items: &[FixFieldItem]) -> Vec<Range<usize>> {
+                #group_ranges
+            }
+        }
+
+        #[derive(Debug, Default)]
+        pub struct #builder_name {
+            #(#builder_fields,)*
+            #extra_builder_field
+        }
+
+        impl #cls_name {
+            pub fn builder() -> #builder_name {
+                Default::default()
+            }
+        }
+
+        impl From<#cls_name> for #builder_name {
+            #[allow(unused_variables)]
+            fn from(value: #cls_name) -> Self {
+                Self {
+                    #(#builder_from)*
+                    #extra_builder_from
+                }
+            }
+        }
+
+        impl #builder_name {
+            #(#builder_setters)*
+            #extra_builder_setter
+
+            #[allow(unused_mut)]
+            pub fn build(self) -> Result<#cls_name, FixBuildError> {
+                let mut missing = Vec::new();
+                #(#builder_checks)*
+                if !missing.is_empty() {
+                    return Err(FixBuildError::MissingRequiredFields(missing));
+                }
+
+                Ok(#cls_name {
+                    #(#builder_assigns)*
+                    #extra_builder_assign
+                })
+            }
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+/// Code handling `extra_fields` of group entries
+#[derive(Default)]
+struct ExtraFieldsCode {
+    field: TokenStream,
+    encode: TokenStream,
+    decode: TokenStream,
+    builder_field: TokenStream,
+    builder_setter: TokenStream,
+    builder_assign: TokenStream,
+    builder_from: TokenStream,
+}
+
+/// Comma separated list of every field ID reachable from references
+fn tags_as_code(refs: &[Reference], ctx: &SpecContext) -> TokenStream {
+    let mut tags = Vec::new();
+    ctx.collect_tags(refs, &mut tags);
+
+    quote!(#(#tags::FIX_KEY),*)
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct HeaderSpec {
+    #[serde(default)]
+    overlay: OverlayMode,
     #[serde(rename = "$value", default)]
     refs: Vec<Reference>,
 }
 
 impl HeaderSpec {
-    fn as_code(&self) -> String {
-        spec_as_code("MessageHeader", &self.refs)
+    fn as_code(&self, ctx: &SpecContext) -> TokenStream {
+        let doc = refs_doc("Standard message header".to_string(), None, &self.refs);
+        let code = component_as_code(
+            "MessageHeader",
+            "StandardHeader",
+            doc,
+            &self.refs,
+            false,
+            ctx,
+        );
+        let session_fields = self.session_fields_as_code();
+
+        quote! {
+            #code
+
+            impl MessageHeaderBuilder {
+                /// Fill fields managed by session, and message type
+                #[allow(unused_variables)]
+                pub fn with_session<S>(mut self, msg_type: &str, session: &mut S) -> Self
+                where
+                    S: FixSessionContext + ?Sized,
+                {
+                    #(#session_fields)*
+                    self
+                }
+            }
+        }
+    }
+
+    fn session_fields_as_code(&self) -> Vec<TokenStream> {
+        let values = [
+            ("MsgType", quote!(msg_type.as_bytes())),
+            ("SenderCompID", quote!(session.sender_comp_id().as_bytes())),
+            ("TargetCompID", quote!(session.target_comp_id().as_bytes())),
+            (
+                "MsgSeqNum",
+                quote!(&to_fix_bytes(&session.next_msg_seq_num())),
+            ),
+            (
+                "SendingTime",
+                quote!(&to_fix_bytes(&session.sending_time())),
+            ),
+        ];
+
+        values
+            .iter()
+            .filter_map(|(name, value)| {
+                self.refs.iter().find_map(|x| match x {
+                    Reference::FieldRef(x) if x.name == *name => {
+                        let field_name = x.as_field_name();
+                        let class_name = x.as_class_name();
+                        Some(quote! {
+                            self.#field_name = #class_name::from_fix_value(#value).ok();
+                        })
+                    }
+                    _ => None,
+                })
+            })
+            .collect()
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct TrailerSpec {
+    #[serde(default)]
+    overlay: OverlayMode,
     #[serde(rename = "$value", default)]
     refs: Vec<Reference>,
 }
 
 impl TrailerSpec {
-    fn as_code(&self) -> String {
-        spec_as_code("MessageTrailer", &self.refs)
+    fn as_code(&self, ctx: &SpecContext) -> TokenStream {
+        let doc = refs_doc("Standard message trailer".to_string(), None, &self.refs);
+        component_as_code(
+            "MessageTrailer",
+            "StandardTrailer",
+            doc,
+            &self.refs,
+            false,
+            ctx,
+        )
     }
 }
 
 #[derive(Debug, Deserialize, Serialize, PartialEq)]
 pub struct Component {
     name: String,
+    #[serde(default)]
+    overlay: OverlayMode,
+    /// Documentation from dictionary, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    doc: Option<String>,
     #[serde(rename = "$value", default)]
     refs: Vec<Reference>,
 }
 
 impl Component {
-    fn as_code(&self) -> String {
-        spec_as_code(&self.name, &self.refs)
+    fn as_code(&self, ctx: &SpecContext) -> TokenStream {
+        let doc = refs_doc(
+            format!("{} component", self.name),
+            self.doc.as_deref(),
+            &self.refs,
+        );
+        component_as_code(&self.name, &self.name, doc, &self.refs, false, ctx)
     }
 }
 
@@ -509,266 +1045,751 @@ pub struct ComponentSpec {
 // =====================================
 // Message spec
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
 pub struct MessagesSpec {
-    #[serde(rename = "$value")]
+    #[serde(rename = "$value", default)]
     items: Vec<Message>,
 }
 
+impl MessagesSpec {
+    fn as_code(&self) -> TokenStream {
+        let items: Vec<_> = self.items.iter().filter(|x| x.is_base_scenario()).collect();
+        let names: Vec<_> = items.iter().map(|x| ident(&x.name)).collect();
+        let cls_names: Vec<_> = items.iter().map(|x| x.message_cls_name()).collect();
+
+        quote! {
+            #[derive(Debug, PartialEq)]
+            #[allow(clippy::large_enum_variant)]
+            pub enum Message {
+                #(#names(#cls_names),)*
+            }
+
+            impl Message {
+                /// Decode any known message, using its MsgType (35) field
+                pub fn decode_any(items: &[FixFieldItem]) -> Result<Self, FixParseError> {
+                    let context = FixFieldContext::new(35, "MsgType");
+                    let msg_type = items
+                        .iter()
+                        .find(|x| x.tag == 35)
+                        .ok_or_else(|| FixParseError::MissingRequiredField(context.clone()))?;
+
+                    match std::str::from_utf8(msg_type.value)? {
+                        #(#cls_names::MESSAGE_TYPE => Ok(Self::#names(#cls_names::decode_items(items)?)),)*
+                        _ => Err(FixParseError::InvalidMsgType(context.with_value(msg_type.value))),
+                    }
+                }
+
+                pub fn msg_type(&self) -> &'static str {
+                    match self {
+                        #(Self::#names(_) => #cls_names::MESSAGE_TYPE,)*
+                    }
+                }
+
+                pub fn dest(&self) -> MessageDest {
+                    match self {
+                        #(Self::#names(_) => #cls_names::MESSAGE_DEST,)*
+                    }
+                }
+
+                pub fn header(&self) -> &MessageHeader {
+                    match *self {
+                        #(Self::#names(ref x) => &x.header,)*
+                    }
+                }
+
+                pub fn header_mut(&mut self) -> &mut MessageHeader {
+                    match *self {
+                        #(Self::#names(ref mut x) => &mut x.header,)*
+                    }
+                }
+            }
+
+            impl AsFixMessage for Message {
+                fn encode_message<W>(&self, writer: &mut W) -> io::Result<()>
+                where
+                    W: Write
+                {
+                    match self {
+                        #(Self::#names(x) => x.encode_message(writer),)*
+                    }
+                }
+            }
+
+            impl FromFixMessage for Message {
+                fn decode_items(items: &[FixFieldItem]) -> Result<Self, FixParseError> {
+                    Self::decode_any(items)
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, PartialEq)]
 pub struct Message {
     msgcat: MessageCategory,
     msgtype: String,
     name: String,
+    #[serde(default)]
+    overlay: OverlayMode,
+    /// Orchestra scenario, when message is not the base one for its MsgType
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scenario: Option<String>,
+    /// Documentation from dictionary, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    doc: Option<String>,
     #[serde(rename = "$value", default)]
     refs: Vec<Reference>,
 }
 
 impl Message {
-    fn message_cls_name(&self) -> String {
-        format!("Message{}", self.name)
+    /// Messages decoded from their MsgType (35), scenario ones have to be decoded explicitly
+    fn is_base_scenario(&self) -> bool {
+        self.scenario.is_none()
+    }
+
+    fn message_cls_name(&self) -> Ident {
+        format_ident!("Message{}", self.name)
     }
 
-    fn message_dest(&self) -> &'static str {
+    fn message_dest(&self) -> TokenStream {
         match &self.msgcat {
-            MessageCategory::Admin => "MessageDest::Admin",
-            MessageCategory::App => "MessageDest::App",
+            MessageCategory::Admin => quote!(MessageDest::Admin),
+            MessageCategory::App => quote!(MessageDest::App),
+        }
+    }
+
+    fn as_doc(&self) -> TokenStream {
+        let category = match self.msgcat {
+            MessageCategory::Admin => "session",
+            MessageCategory::App => "application",
+        };
+        let mut title = format!(
+            "{} {} message, MsgType `{}`",
+            self.name, category, self.msgtype
+        );
+        if let Some(scenario) = &self.scenario {
+            title.push_str(&format!(
+                ".\n\nScenario `{}`: it is not decoded by `Message::decode_any`, use `decode_items` instead.",
+                scenario
+            ));
         }
+        refs_doc(title, self.doc.as_deref(), &self.refs)
     }
 
-    fn as_code(&self) -> String {
-        let gen = generate_ref_code(&self.refs, &self.name);
+    fn as_code(&self, ctx: &SpecContext) -> TokenStream {
+        let RefGeneratedCode {
+            classes,
+            fields,
+            fields_encode,
+            fields_decode,
+            builder_fields,
+            builder_setters,
+            builder_checks,
+            builder_assigns,
+            group_ranges,
+            ..
+        } = generate_ref_code(&self.refs, &self.name, ctx);
+        let message_cls_name = self.message_cls_name();
+        let builder_name = format_ident!("{}Builder", message_cls_name);
+        let message_name = &self.name;
+        let message_dest = self.message_dest();
+        let msg_type = &self.msgtype;
+        let tags = tags_as_code(&self.refs, ctx);
+        let doc = self.as_doc();
+
+        quote! {
+            #(#classes)*
+
+            #doc
+            #[derive(Debug, PartialEq)]
+            pub struct #message_cls_name {
+                pub header: MessageHeader,
+                pub trailer: MessageTrailer,
+
+                #(#fields,)*
+
+                /// Fields not defined in dictionary, in wire order
+                pub extra_fields: Vec<FixExtraField>,
+            }
+
+            impl #message_cls_name {
+                pub const MESSAGE_DEST: MessageDest = #message_dest;
+                pub const MESSAGE_TYPE: &'static str = #msg_type;
+                pub const FIX_TAGS: &'static [u32] = &[#tags];
+            }
 
-        format!(
-            "
-{classes}
+            impl AsFixMessage for #message_cls_name {
+                fn encode_message<W>(&self, writer: &mut W) -> io::Result<()>
+                where
+                    W: Write
+                {
+                    self.header.encode_message(writer)?;
+                    #(#fields_encode)*
+                    encode_extra_fields(writer, &self.extra_fields)?;
+                    self.trailer.encode_message(writer)?;
+
+                    Ok(())
+                }
+            }
 
-#[derive(Debug, PartialEq)]
-pub struct {message_cls_name} {{
-    // Common fields
-    pub header: MessageHeader,
-    pub trailer: MessageTrailer,
+            impl #message_cls_name {
+                #[allow(unused_variables)]
+                fn decode_fields(items: &[FixFieldItem]) -> Result<Self, FixParseError> {
+                    Ok(Self {
+                        header: MessageHeader::decode_items(items)?,
+                        #(#fields_decode)*
+                        trailer: MessageTrailer::decode_items(items)?,
+                        extra_fields: decode_extra_fields(
+                            items,
+                            &[MessageHeader::FIX_TAGS, Self::FIX_TAGS, MessageTrailer::FIX_TAGS],
+                            &Self::group_ranges(items),
+                        ),
+                    })
+                }
+
+                /// Items of repeating groups, whose unknown fields are kept by group entries
+                fn group_ranges(items: &[FixFieldItem]) -> Vec<Range<usize>> {
+                    let mut ranges = MessageHeader::group_ranges(items);
+                    #(#group_ranges)*
+                    ranges.extend(MessageTrailer::group_ranges(items));
+                    ranges
+                }
+            }
 
-    // Custom fields
-{fields}
-}}
+            impl FromFixMessage for #message_cls_name {
+                fn decode_items(items: &[FixFieldItem]) -> Result<Self, FixParseError> {
+                    Self::decode_fields(items).map_err(|e| e.with_parent(#message_name))
+                }
+            }
 
-impl {message_cls_name} {{
-    pub const MESSAGE_DEST: MessageDest = {message_dest};
-    pub const MESSAGE_TYPE: &'static str = \"{msg_type}\";
-}}
+            #[derive(Debug, Default)]
+            pub struct #builder_name {
+                header: MessageHeaderBuilder,
+                trailer: MessageTrailerBuilder,
+                #(#builder_fields,)*
+                extra_fields: Vec<FixExtraField>,
+            }
 
-impl AsFixMessage for {message_cls_name} {{
-    fn encode_message<W>(&self, writer: &mut W) -> io::Result<()>
-    where
-        W: Write
-    {{
-        self.header.encode_message(writer)?;
-{fields_encode}
-        self.trailer.encode_message(writer)?;
+            impl #message_cls_name {
+                pub fn builder() -> #builder_name {
+                    Default::default()
+                }
+            }
 
-        Ok(())
-    }}
-}}
-
-impl FromFixMessage for {message_cls_name} {{
-    #[allow(unused_variables)]
-    fn decode_message(items: &FixFieldItems) -> Result<Self, FixParseError> {{
-        Ok(Self {{
-            header: MessageHeader::decode_message(items)?,
-{fields_decode}
-            trailer: MessageTrailer::decode_message(items)?,
-        }})
-    }}
-}}
-",
-            message_cls_name = self.message_cls_name(),
-            message_dest = self.message_dest(),
-            msg_type = self.msgtype,
-            classes = gen.classes.join("\n"),
-            fields = gen.fields.join("\n"),
-            fields_encode = gen.fields_encode.join("\n"),
-            fields_decode = gen.fields_decode.join("\n"),
-        )
+            impl #builder_name {
+                pub fn header(mut self, value: MessageHeader) -> Self {
+                    self.header = value.into();
+                    self
+                }
+
+                pub fn trailer(mut self, value: MessageTrailer) -> Self {
+                    self.trailer = value.into();
+                    self
+                }
+
+                pub fn extra_fields(mut self, value: Vec<FixExtraField>) -> Self {
+                    self.extra_fields = value;
+                    self
+                }
+
+                #(#builder_setters)*
+
+                /// Update header fields one by one
+                pub fn with_header<F>(mut self, f: F) -> Self
+                where
+                    F: FnOnce(MessageHeaderBuilder) -> MessageHeaderBuilder,
+                {
+                    self.header = f(self.header);
+                    self
+                }
+
+                /// Fill header fields managed by session and MsgType (35)
+                pub fn with_session<S>(mut self, session: &mut S) -> Self
+                where
+                    S: FixSessionContext + ?Sized,
+                {
+                    self.header = self.header.with_session(#message_cls_name::MESSAGE_TYPE, session);
+                    self
+                }
+
+                pub fn build(self) -> Result<#message_cls_name, FixBuildError> {
+                    let mut missing = Vec::new();
+                    let header = self.header.build().map_err(|e| missing.extend_from_slice(e.missing_fields())).ok();
+                    #(#builder_checks)*
+                    let trailer = self.trailer.build().map_err(|e| missing.extend_from_slice(e.missing_fields())).ok();
+                    if !missing.is_empty() {
+                        return Err(FixBuildError::MissingRequiredFields(missing));
+                    }
+
+                    Ok(#message_cls_name {
+                        header: header.unwrap(),
+                        trailer: trailer.unwrap(),
+                        #(#builder_assigns)*
+                        extra_fields: self.extra_fields,
+                    })
+                }
+            }
+        }
     }
 }
 
 // =====================================
 // Field spec
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct FieldValue {
     #[serde(rename = "enum")]
     value: String,
     description: String,
+    /// Documentation from dictionary, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    doc: Option<String>,
 }
 
 impl FieldValue {
     fn as_rust_desc(&self) -> String {
         assert!(!self.description.is_empty());
-        if !char::is_ascii_alphabetic(&self.description.chars().nth(0).unwrap()) {
+        if !char::is_ascii_alphabetic(&self.description.chars().next().unwrap()) {
             format!("Value{}", self.description.to_case(Case::UpperCamel))
         } else {
             self.description.to_case(Case::UpperCamel)
         }
     }
+
+    fn as_variant(&self) -> Ident {
+        ident(&self.as_rust_desc())
+    }
+
+    fn as_doc(&self) -> TokenStream {
+        let mut paragraphs = vec![format!("`{}`: {}", self.value, self.description)];
+        paragraphs.extend(self.doc.clone());
+        doc_attrs(&paragraphs.join("\n\n"))
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct FieldDef {
     name: String,
     number: u32,
     #[serde(rename = "type")]
     field_type: FieldType,
+    #[serde(default)]
+    overlay: OverlayMode,
+    /// Documentation from dictionary, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    doc: Option<String>,
     #[serde(rename = "value", default)]
     values: Vec<FieldValue>,
 }
 
 impl FieldDef {
-    fn as_code(&self) -> String {
+    /// Format value for `fmt::Display`
+    fn as_display_value(&self) -> TokenStream {
+        match self.field_type {
+            FieldType::Data => quote!(String::from_utf8_lossy(&self.value)),
+            _ if self.field_type.is_multiple_value() => {
+                quote!(String::from_utf8_lossy(&to_fix_bytes(&self.value)))
+            }
+            _ => quote!(self.value),
+        }
+    }
+
+    /// Override of `AsFixMessageField::encode_message` for data / length pairs
+    fn as_encode_message_code(&self, spec: &FieldSpec) -> TokenStream {
+        if let Some(length) = spec.length_field_of(self) {
+            let doc = format!(
+                " {} ({}) is written first, using actual data length",
+                length.name, length.number
+            );
+            let length_id = number(length.number);
+
+            quote! {
+                #[doc = #doc]
+                fn encode_message<W>(&self, writer: &mut W) -> io::Result<()>
+                where
+                    W: Write,
+                {
+                    write!(writer, "{}={}\x01{}=", #length_id, self.value.len(), Self::FIX_KEY)?;
+                    self.encode_fix_value(writer)?;
+                    write!(writer, "\x01")
+                }
+            }
+        } else if let Some(data) = spec.data_field_of(self) {
+            let doc = format!(
+                " Nothing is written: {} ({}) writes its length itself",
+                data.name, data.number
+            );
+
+            quote! {
+                #[doc = #doc]
+                fn encode_message<W>(&self, _writer: &mut W) -> io::Result<()>
+                where
+                    W: Write,
+                {
+                    Ok(())
+                }
+            }
+        } else {
+            TokenStream::new()
+        }
+    }
+
+    /// Tag, type, dictionary documentation, and header / trailer / messages using field
+    fn as_doc(&self, users: &[&str]) -> TokenStream {
+        let mut paragraphs = vec![format!(
+            "{} ({}), FIX type `{}`",
+            self.name,
+            self.number,
+            self.field_type.as_fix_name()
+        )];
+        paragraphs.extend(self.doc.clone());
+        if !users.is_empty() {
+            paragraphs.push(format!("Used by: {}", users.join(", ")));
+        }
+        doc_attrs(&paragraphs.join("\n\n"))
+    }
+
+    fn as_code(&self, spec: &FieldSpec, users: &[&str], options: &GeneratorOptions) -> TokenStream {
+        let doc = self.as_doc(users);
+
         if self.values.is_empty() {
-            format!(
-                "
-#[derive(Debug, PartialEq)]
-pub struct {field_name} {{
-    pub value: {content_type}
-}}
-
-impl {field_name} {{
-    pub fn new(value: {content_type}) -> Self {{
-        Self {{ value }}
-    }}
-}}
-
-impl fmt::Display for {field_name} {{
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {{
-        write!(f, \"{field_name_upper}({{}})\", self.value)
-    }}
-}}
-
-impl AsFixMessageField for {field_name} {{
-    const FIX_KEY: u32 = {field_id};
-
-    fn encode_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
-    where
-        W: Write,
-    {{
-        write!(writer, \"{{}}\", self.value)
-    }}
-}}
-
-impl FromFixMessageField for {field_name} {{
-    fn from_fix_value(value: &[u8]) -> Result<Self, FixParseError> {{
-        let value = std::str::from_utf8(value)?;
-        let value = value.parse().map_err(|_e| FixParseError::InvalidData)?;
-        Ok(Self {{ value }})
-    }}
-}}
-
-",
-                field_name = self.name,
-                field_name_upper = self.name.to_case(Case::UpperSnake),
-                field_id = self.number,
-                content_type = self.field_type.as_rust_type(),
-            )
+            let struct_code = self.struct_as_code(
+                self.field_type.as_rust_type(),
+                format_ident!("InvalidFormat"),
+                spec,
+            );
+
+            quote! {
+                #doc
+                #struct_code
+            }
+        } else if self.field_type.is_multiple_value() {
+            let item_name = format_ident!("{}Item", self.name);
+            let item_code = self.item_enum_as_code(&item_name, options);
+            let struct_code = self.struct_as_code(
+                quote!(Vec<#item_name>),
+                format_ident!("InvalidEnumValue"),
+                spec,
+            );
+
+            quote! {
+                #item_code
+                #doc
+                #struct_code
+            }
         } else {
-            assert!(matches!(
-                self.field_type,
-                FieldType::String
-                    | FieldType::Char
-                    | FieldType::Int
-                    | FieldType::MultipleValueString
-                    | FieldType::Boolean
-                    | FieldType::NumInGroup
-            ));
+            let enum_code = self.enum_as_code(options);
 
-            let field_names = self
-                .values
-                .iter()
-                .map(|x| format!("\t{},", x.as_rust_desc()))
-                .collect::<Vec<_>>()
-                .join("\n");
+            quote! {
+                #doc
+                #enum_code
+            }
+        }
+    }
 
-            let as_field_descriptions = self
-                .values
-                .iter()
-                .map(|x| format!("\t\t\tSelf::{} => \"{}\",", x.as_rust_desc(), x.description))
-                .collect::<Vec<_>>()
-                .join("\n");
+    fn struct_as_code(
+        &self,
+        content_type: TokenStream,
+        parse_error: Ident,
+        spec: &FieldSpec,
+    ) -> TokenStream {
+        let field_name = ident(&self.name);
+        let fix_name = &self.name;
+        let field_id = number(self.number);
+        let display_format = format!("{}({{}})", self.name.to_case(Case::UpperSnake));
+        let display_value = self.as_display_value();
+        let encode_message = self.as_encode_message_code(spec);
+
+        quote! {
+            #[derive(Debug, PartialEq)]
+            pub struct #field_name {
+                pub value: #content_type
+            }
 
-            let as_field_values = self
-                .values
-                .iter()
-                .map(|x| format!("\t\t\t\tSelf::{} => \"{}\",", x.as_rust_desc(), x.value))
-                .collect::<Vec<_>>()
-                .join("\n");
+            impl #field_name {
+                pub fn new(value: #content_type) -> Self {
+                    Self { value }
+                }
+            }
 
-            let from_field_values = self
-                .values
-                .iter()
-                .map(|x| format!("\t\t\tb\"{}\" => Ok(Self::{}),", x.value, x.as_rust_desc()))
-                .collect::<Vec<_>>()
-                .join("\n");
-
-            format!(
-                "
-#[derive(Debug, PartialEq)]
-pub enum {field_name} {{
-{field_names}
-}}
-
-impl fmt::Display for {field_name} {{
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {{
-        write!(f, \"{{}}\", match *self {{
-{as_field_descriptions}
-        }})
-    }}
-}}
-
-impl AsFixMessageField for {field_name} {{
-    const FIX_KEY: u32 = {field_id};
-
-    fn encode_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
-    where
-        W: Write,
-    {{
-        write!(
-            writer,
-            \"{{}}\",
-            match *self {{
-{as_field_values}
-            }}
-        )
-    }}
-}}
-
-impl FromFixMessageField for {field_name} {{
-    fn from_fix_value(value: &[u8]) -> Result<Self, FixParseError> {{
-        match value {{
-{from_field_values}
-            _ => Err(FixParseError::InvalidData),
-        }}
-    }}
-}}
-
-",
-                field_name = self.name,
-                field_id = self.number,
-                field_names = field_names,
-                as_field_descriptions = as_field_descriptions,
-                as_field_values = as_field_values,
-                from_field_values = from_field_values,
-            )
+            impl fmt::Display for #field_name {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, #display_format, #display_value)
+                }
+            }
+
+            impl AsFixMessageField for #field_name {
+                const FIX_KEY: u32 = #field_id;
+                const FIX_NAME: &'static str = #fix_name;
+
+                fn encode_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+                where
+                    W: Write,
+                {
+                    self.value.write_fix_value(writer)
+                }
+
+                #encode_message
+            }
+
+            impl FromFixMessageField for #field_name {
+                fn from_fix_value(value: &[u8]) -> Result<Self, FixParseError> {
+                    let parsed = FixFieldValue::read_fix_value(value)
+                        .ok_or_else(|| FixParseError::#parse_error(Self::error_context(Some(value))))?;
+                    Ok(Self { value: parsed })
+                }
+            }
+        }
+    }
+
+    fn check_enum_type(&self) {
+        assert!(self.field_type.supports_enum_values());
+    }
+
+    fn as_enum_variants(&self, options: &GeneratorOptions) -> Vec<TokenStream> {
+        let mut variants: Vec<_> = self
+            .values
+            .iter()
+            .map(|x| {
+                let doc = x.as_doc();
+                let variant = x.as_variant();
+                quote!(#doc #variant)
+            })
+            .collect();
+
+        if options.enable_unknown_enum_values {
+            variants.push(quote! {
+                /// Value not defined in dictionary
+                UnknownValue(String)
+            });
+        }
+        variants
+    }
+
+    fn as_enum_descriptions(&self, options: &GeneratorOptions) -> Vec<TokenStream> {
+        let mut descriptions: Vec<_> = self
+            .values
+            .iter()
+            .map(|x| {
+                let variant = x.as_variant();
+                let description = &x.description;
+                quote!(Self::#variant => #description,)
+            })
+            .collect();
+
+        if options.enable_unknown_enum_values {
+            descriptions.push(quote!(Self::UnknownValue(ref value) => value.as_str(),));
+        }
+        descriptions
+    }
+
+    fn as_enum_values(&self, options: &GeneratorOptions) -> Vec<TokenStream> {
+        let mut values: Vec<_> = self
+            .values
+            .iter()
+            .map(|x| {
+                let variant = x.as_variant();
+                let value = &x.value;
+                quote!(Self::#variant => #value,)
+            })
+            .collect();
+
+        if options.enable_unknown_enum_values {
+            values.push(quote!(Self::UnknownValue(ref value) => value.as_str(),));
+        }
+        values
+    }
+
+    /// Match arms from wire value, wrapping result in given type constructor
+    fn as_enum_from_values(&self, wrapper: &Ident) -> Vec<TokenStream> {
+        self.values
+            .iter()
+            .map(|x| {
+                let variant = x.as_variant();
+                let value = Literal::byte_string(x.value.as_bytes());
+                quote!(#value => #wrapper(Self::#variant),)
+            })
+            .collect()
+    }
+
+    fn enum_as_code(&self, options: &GeneratorOptions) -> TokenStream {
+        self.check_enum_type();
+
+        let field_name = ident(&self.name);
+        let fix_name = &self.name;
+        let field_id = number(self.number);
+        let field_names = self.as_enum_variants(options);
+        let as_field_descriptions = self.as_enum_descriptions(options);
+        let as_field_values = self.as_enum_values(options);
+        let from_field_values = self.as_enum_from_values(&format_ident!("Ok"));
+        let unknown_value = if options.enable_unknown_enum_values {
+            quote!(value => Ok(Self::UnknownValue(std::str::from_utf8(value)?.to_string())),)
+        } else {
+            quote!(_ => Err(FixParseError::InvalidEnumValue(Self::error_context(Some(value)))),)
+        };
+
+        quote! {
+            #[derive(Debug, PartialEq)]
+            pub enum #field_name {
+                #(#field_names,)*
+            }
+
+            impl fmt::Display for #field_name {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "{}", match *self {
+                        #(#as_field_descriptions)*
+                    })
+                }
+            }
+
+            impl AsFixMessageField for #field_name {
+                const FIX_KEY: u32 = #field_id;
+                const FIX_NAME: &'static str = #fix_name;
+
+                fn encode_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+                where
+                    W: Write,
+                {
+                    write!(
+                        writer,
+                        "{}",
+                        match *self {
+                            #(#as_field_values)*
+                        }
+                    )
+                }
+            }
+
+            impl FromFixMessageField for #field_name {
+                fn from_fix_value(value: &[u8]) -> Result<Self, FixParseError> {
+                    match value {
+                        #(#from_field_values)*
+                        #unknown_value
+                    }
+                }
+            }
+        }
+    }
+
+    /// Single value of a multiple value field
+    fn item_enum_as_code(&self, item_name: &Ident, options: &GeneratorOptions) -> TokenStream {
+        self.check_enum_type();
+
+        let field_names = self.as_enum_variants(options);
+        let as_field_descriptions = self.as_enum_descriptions(options);
+        let as_field_values = self.as_enum_values(options);
+        let from_field_values = self.as_enum_from_values(&format_ident!("Some"));
+        let unknown_value = if options.enable_unknown_enum_values {
+            quote!(value => Some(Self::UnknownValue(std::str::from_utf8(value).ok()?.to_string())),)
+        } else {
+            quote!(_ => None,)
+        };
+        let doc = format!(" Single value of {} multiple value field", self.name);
+
+        quote! {
+            #[doc = #doc]
+            #[derive(Debug, PartialEq)]
+            pub enum #item_name {
+                #(#field_names,)*
+            }
+
+            impl fmt::Display for #item_name {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "{}", match *self {
+                        #(#as_field_descriptions)*
+                    })
+                }
+            }
+
+            impl FixFieldValue for #item_name {
+                fn write_fix_value<W>(&self, writer: &mut W) -> io::Result<()>
+                where
+                    W: Write,
+                {
+                    write!(
+                        writer,
+                        "{}",
+                        match *self {
+                            #(#as_field_values)*
+                        }
+                    )
+                }
+
+                fn read_fix_value(value: &[u8]) -> Option<Self> {
+                    match value {
+                        #(#from_field_values)*
+                        #unknown_value
+                    }
+                }
+            }
+
+            impl FixMultipleValue for #item_name {}
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
 pub struct FieldSpec {
-    #[serde(rename = "field")]
+    #[serde(rename = "field", default)]
     items: Vec<FieldDef>,
 }
 
+impl FieldSpec {
+    /// Get LENGTH field of a DATA field, named "{name}Len" or "{name}Length"
+    fn length_field_of(&self, field: &FieldDef) -> Option<&FieldDef> {
+        if field.field_type != FieldType::Data {
+            return None;
+        }
+
+        let names = [
+            format!("{}Len", field.name),
+            format!("{}Length", field.name),
+        ];
+        self.items
+            .iter()
+            .find(|x| x.field_type == FieldType::Length && names.contains(&x.name))
+    }
+
+    /// Get DATA field whose length is given by a LENGTH field
+    fn data_field_of(&self, field: &FieldDef) -> Option<&FieldDef> {
+        if field.field_type != FieldType::Length {
+            return None;
+        }
+
+        self.items
+            .iter()
+            .find(|x| matches!(self.length_field_of(x), Some(length) if length.name == field.name))
+    }
+
+    fn field_tags_as_code(&self) -> TokenStream {
+        let mut tags: Vec<u32> = self.items.iter().map(|x| x.number).collect();
+        tags.sort_unstable();
+        tags.dedup();
+        let tags = tags.into_iter().map(number);
+
+        quote! {
+            /// ID of every field of the dictionary, sorted
+            pub const FIELD_TAGS: &[u32] = &[#(#tags),*];
+        }
+    }
+
+    fn data_fields_as_code(&self) -> TokenStream {
+        let (lengths, datas): (Vec<_>, Vec<_>) = self
+            .items
+            .iter()
+            .filter_map(|x| {
+                self.length_field_of(x)
+                    .map(|length| (number(length.number), number(x.number)))
+            })
+            .unzip();
+
+        quote! {
+            /// (length field ID, data field ID) pairs, required to scan data fields containing SOH
+            pub const DATA_FIELDS: &[(u32, u32)] = &[#((#lengths, #datas)),*];
+        }
+    }
+}
+
 // =====================================
 // Fix spec
 
@@ -791,20 +1812,40 @@ pub struct FixSpec {
     component: Option<ComponentSpec>,
 
     /// Know networks and standardized messages
-    #[serde(rename = "messages")]
+    #[serde(rename = "messages", default)]
     message: MessagesSpec,
 
     /// Message known fields
-    #[serde(rename = "fields")]
+    #[serde(rename = "fields", default)]
     field: FieldSpec,
 }
 
+/// Options changing generated code
+#[derive(Debug, Clone, Default)]
+pub struct GeneratorOptions {
+    /// Add `UnknownValue(String)` variant to every enum, keeping values missing from dictionary
+    pub enable_unknown_enum_values: bool,
+    /// Path of `openfix_messages` crate used by generated code (ie: `"openfix_messages"`).
+    ///
+    /// When not set, generated code uses `crate::prelude` and `chrono` crate.
+    pub runtime_crate: Option<String>,
+}
+
+/// Generated code, before formatting
+#[derive(Debug, Clone)]
+pub struct GeneratedCode {
+    /// Content of `fields` module
+    pub fields: TokenStream,
+    /// Content of `messages` module, which uses `super::fields`
+    pub messages: TokenStream,
+}
+
 impl FixSpec {
     pub fn generate_specfile<P>(
         &self,
         out_dir: P,
         src_filename: P,
-        enable_rustfmt: bool,
+        options: &GeneratorOptions,
     ) -> anyhow::Result<()>
     where
         P: AsRef<Path>,
@@ -817,104 +1858,616 @@ impl FixSpec {
             };
         }
 
-        macro_rules! open_file_writer {
-            ($($arg:tt)*)  => {
-                BufWriter::new(fs::File::create(out_file_path!($($arg)*))?)
-            };
-        }
-
         // Dump parsed XML
-        let f_parsed = open_file_writer!("{}.parsed.json", stem);
+        let f_parsed = BufWriter::new(fs::File::create(out_file_path!("{}.parsed.json", stem))?);
         serde_json::to_writer_pretty(f_parsed, self)?;
 
-        // Generate code
-        let mut f_fields = open_file_writer!("{}_fields.rs", stem);
-        let mut f_messages = open_file_writer!("{}_messages.rs", stem);
-
-        write!(f_fields, "{}", CODE_HEADER)?;
-        write!(
-            f_fields,
-            "
-#[allow(unused_imports)]
-use std::{{fmt, io::{{self, Write}}}};
+        let code = self.generate_code(options)?;
 
-#[allow(unused_imports)]
-use crate::prelude::*;
-
-",
+        fs::write(
+            out_file_path!("{}_fields.rs", stem),
+            format_code(code.fields)?,
+        )?;
+        fs::write(
+            out_file_path!("{}_messages.rs", stem),
+            format_code(code.messages)?,
         )?;
-        write!(f_messages, "{}", CODE_HEADER)?;
-        write!(
-            f_messages,
-            "
-#[allow(unused_imports)]
-use super::fields::*;
 
-#[allow(unused_imports)]
-use std::{{fmt, io::{{self, Write}}}};
+        Ok(())
+    }
 
-#[allow(unused_imports)]
-use crate::prelude::*;
+    /// Generate fields and messages code, dictionary is validated first
+    pub fn generate_code(&self, options: &GeneratorOptions) -> anyhow::Result<GeneratedCode> {
+        // Code generation expects every reference to be resolved
+        self.validate()?;
 
-",
-        )?;
+        let ctx = SpecContext::new(self);
+        let code_header = code_header(options)?;
 
         // Generate fields
-        for field in &self.field.items {
-            write!(f_fields, "{}", field.as_code())?;
+        let data_fields = self.field.data_fields_as_code();
+        let field_tags = self.field.field_tags_as_code();
+        let field_users = ctx.field_users(self);
+        let fields = self.field.items.iter().map(|field| {
+            let users = field_users
+                .get(field.name.as_str())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            field.as_code(&self.field, users, options)
+        });
+
+        let fields_code = quote! {
+            #code_header
+            #data_fields
+            #field_tags
+            #(#fields)*
+        };
+
+        // Generate headers, trailers, components and messages
+        let header = self.header.as_ref().map(|x| x.as_code(&ctx));
+        let trailer = self.trailer.as_ref().map(|x| x.as_code(&ctx));
+        let components = self
+            .component
+            .iter()
+            .flat_map(|x| x.items.iter())
+            .map(|x| x.as_code(&ctx));
+        let messages = self.message.items.iter().map(|x| x.as_code(&ctx));
+        let message_enum = self.message.as_code();
+        let appl_ver_id = self.appl_ver_id().map(|appl_ver_id| {
+            quote! {
+                /// ApplVerID (1128) of messages, when sent over FIXT 1.1
+                pub const APPL_VER_ID: &str = #appl_ver_id;
+            }
+        });
+
+        let messages_code = quote! {
+            #code_header
+
+            #[allow(unused_imports)]
+            use super::fields::*;
+
+            #header
+            #trailer
+            #(#components)*
+            #(#messages)*
+            #message_enum
+            #appl_ver_id
+        };
+
+        Ok(GeneratedCode {
+            fields: fields_code,
+            messages: messages_code,
+        })
+    }
+}
+
+// =====================================
+// Application over transport dictionary
+
+impl FixSpec {
+    /// ApplVerID (1128) matching dictionary version, if it is an application one
+    fn appl_ver_id(&self) -> Option<&'static str> {
+        match (self.major, self.minor, self.servicepack) {
+            (4, 0, _) => Some("2"),
+            (4, 1, _) => Some("3"),
+            (4, 2, _) => Some("4"),
+            (4, 3, _) => Some("5"),
+            (4, 4, _) => Some("6"),
+            (5, 0, 0) => Some("7"),
+            (5, 0, 1) => Some("8"),
+            (5, 0, 2) => Some("9"),
+            _ => None,
         }
+    }
 
-        // Generate headers
-        if let Some(header) = &self.header {
-            write!(f_messages, "{}", header.as_code())?;
+    /// Use header and trailer of a transport dictionary (ie: FIXT 1.1).
+    ///
+    /// Transport fields missing from application dictionary are added to it.
+    pub fn use_transport(&mut self, transport: &FixSpec) {
+        self.header = transport.header.clone();
+        self.trailer = transport.trailer.clone();
+
+        for field in &transport.field.items {
+            if !self.field.items.iter().any(|x| x.name == field.name) {
+                self.field.items.push(field.clone());
+            }
         }
+    }
+}
 
-        // Generate trailers
-        if let Some(trailer) = &self.trailer {
-            write!(f_messages, "{}", trailer.as_code())?;
+// =====================================
+// Dictionary overlay
+
+/// Add overlay references to base ones, overlay references replacing base ones with the same name
+fn merge_refs(refs: &mut Vec<Reference>, overlay: Vec<Reference>) {
+    for item in overlay {
+        match refs.iter_mut().find(|x| x.name() == item.name()) {
+            Some(x) => *x = item,
+            None => refs.push(item),
         }
+    }
+}
 
-        // Generate components
-        if let Some(component) = &self.component {
-            for item in &component.items {
-                write!(f_messages, "{}", item.as_code())?;
+impl HeaderSpec {
+    fn merge(&mut self, overlay: HeaderSpec) {
+        match overlay.overlay {
+            OverlayMode::Extend => merge_refs(&mut self.refs, overlay.refs),
+            OverlayMode::Replace => *self = overlay,
+        }
+    }
+}
+
+impl TrailerSpec {
+    fn merge(&mut self, overlay: TrailerSpec) {
+        match overlay.overlay {
+            OverlayMode::Extend => merge_refs(&mut self.refs, overlay.refs),
+            OverlayMode::Replace => *self = overlay,
+        }
+    }
+}
+
+impl ComponentSpec {
+    fn merge(&mut self, overlay: ComponentSpec) {
+        for item in overlay.items {
+            match self.items.iter_mut().find(|x| x.name == item.name) {
+                Some(x) if item.overlay == OverlayMode::Extend => {
+                    merge_refs(&mut x.refs, item.refs)
+                }
+                Some(x) => *x = item,
+                None => self.items.push(item),
+            }
+        }
+    }
+}
+
+impl MessagesSpec {
+    fn merge(&mut self, overlay: MessagesSpec) -> anyhow::Result<()> {
+        for item in overlay.items {
+            if let Some(x) = self
+                .items
+                .iter()
+                .filter(|x| x.is_base_scenario() && item.is_base_scenario())
+                .find(|x| x.msgtype == item.msgtype && x.name != item.name)
+            {
+                anyhow::bail!(
+                    "message {} uses MsgType {:?} already used by message {}",
+                    item.name,
+                    item.msgtype,
+                    x.name
+                );
+            }
+
+            match self.items.iter_mut().find(|x| x.name == item.name) {
+                Some(x) if item.overlay == OverlayMode::Extend => {
+                    if x.msgtype != item.msgtype {
+                        anyhow::bail!(
+                            "message {} cannot change MsgType from {:?} to {:?} without overlay='replace'",
+                            item.name,
+                            x.msgtype,
+                            item.msgtype
+                        );
+                    }
+                    merge_refs(&mut x.refs, item.refs);
+                }
+                Some(x) => *x = item,
+                None => self.items.push(item),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FieldSpec {
+    fn merge(&mut self, overlay: FieldSpec) -> anyhow::Result<()> {
+        for item in overlay.items {
+            if let Some(x) = self
+                .items
+                .iter()
+                .find(|x| x.number == item.number && x.name != item.name)
+            {
+                anyhow::bail!(
+                    "field {} uses ID {} already used by field {}",
+                    item.name,
+                    item.number,
+                    x.name
+                );
+            }
+
+            match self.items.iter_mut().find(|x| x.name == item.name) {
+                Some(x) if item.overlay == OverlayMode::Extend => {
+                    if x.number != item.number || x.field_type != item.field_type {
+                        anyhow::bail!(
+                            "field {} cannot change ID or type without overlay='replace'",
+                            item.name
+                        );
+                    }
+                    for value in item.values {
+                        match x.values.iter_mut().find(|v| v.value == value.value) {
+                            Some(v) => *v = value,
+                            None => x.values.push(value),
+                        }
+                    }
+                }
+                Some(x) => *x = item,
+                None => self.items.push(item),
             }
         }
+        Ok(())
+    }
+}
 
-        // Generate messages
-        for message in &self.message.items {
-            write!(f_messages, "{}", message.as_code())?;
+impl FixSpec {
+    /// Apply overlay dictionary on current one.
+    ///
+    /// Overlay items (fields, components, messages, header and trailer) with a name
+    /// already defined are extended, unless they are marked with `overlay='replace'`.
+    pub fn merge(&mut self, overlay: FixSpec) -> anyhow::Result<()> {
+        match (&mut self.header, overlay.header) {
+            (Some(header), Some(overlay)) => header.merge(overlay),
+            (header, overlay @ Some(_)) => *header = overlay,
+            (_, None) => {}
         }
 
-        drop(f_fields);
-        drop(f_messages);
+        match (&mut self.trailer, overlay.trailer) {
+            (Some(trailer), Some(overlay)) => trailer.merge(overlay),
+            (trailer, overlay @ Some(_)) => *trailer = overlay,
+            (_, None) => {}
+        }
 
-        if enable_rustfmt {
-            Command::new("rustfmt")
-                .arg(out_file_path!("{}_fields.rs", stem))
-                .arg(out_file_path!("{}_messages.rs", stem))
-                .status()
-                .expect("rustmft failed");
+        match (&mut self.component, overlay.component) {
+            (Some(component), Some(overlay)) => component.merge(overlay),
+            (component, overlay @ Some(_)) => *component = overlay,
+            (_, None) => {}
         }
 
+        self.message.merge(overlay.message)?;
+        self.field.merge(overlay.field)?;
+        Ok(())
+    }
+}
+
+// =====================================
+// Dictionary validation
+
+/// Problem found in a dictionary
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SpecIssueKind {
+    #[error("unknown field reference: {0}")]
+    UnknownField(String),
+    #[error("unknown component reference: {0}")]
+    UnknownComponent(String),
+    #[error("duplicate name: {0}")]
+    DuplicateName(String),
+    #[error("duplicate field ID {number}, already used by field {other}")]
+    DuplicateFieldNumber { number: u32, other: String },
+    #[error("duplicate MsgType {msg_type:?}, already used by message {other}")]
+    DuplicateMsgType { msg_type: String, other: String },
+    #[error("enum values are not supported on {0:?} fields")]
+    UnsupportedEnumType(FieldType),
+    #[error("duplicate enum value {0:?}")]
+    DuplicateEnumValue(String),
+    #[error("empty enum description")]
+    EmptyEnumDescription,
+    #[error("enum description generates variant {variant}, already used by value {other:?}")]
+    DuplicateEnumVariant { variant: String, other: String },
+    #[error("empty group")]
+    EmptyGroup,
+    #[error("recursive component: {}", .0.join(" -> "))]
+    RecursiveComponent(Vec<String>),
+}
+
+/// Problem found in a dictionary, with its XML location
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("{location}: {kind}")]
+pub struct SpecIssue {
+    /// XPath like location of invalid item (ie: `messages/message[@name='Logon']/field[@name='Foo']`)
+    pub location: String,
+    pub kind: SpecIssueKind,
+}
+
+/// Every problem found in a dictionary
+#[derive(Debug, Clone, PartialEq, Error)]
+pub struct SpecValidationError {
+    pub issues: Vec<SpecIssue>,
+}
+
+impl fmt::Display for SpecValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid FIX dictionary ({} issues)", self.issues.len())?;
+        for issue in &self.issues {
+            write!(f, "\n- {}", issue)?;
+        }
         Ok(())
     }
 }
 
+struct SpecValidator<'a> {
+    fields: HashMap<&'a str, &'a FieldDef>,
+    components: HashMap<&'a str, &'a Component>,
+    issues: Vec<SpecIssue>,
+}
+
+impl<'a> SpecValidator<'a> {
+    fn report(&mut self, location: &str, kind: SpecIssueKind) {
+        self.issues.push(SpecIssue {
+            location: location.to_string(),
+            kind,
+        });
+    }
+
+    fn validate_fields(&mut self, spec: &'a FieldSpec) {
+        let mut numbers = HashMap::new();
+
+        for field in &spec.items {
+            let location = format!("fields/field[@name='{}']", field.name);
+
+            if self.fields.insert(&field.name, field).is_some() {
+                self.report(&location, SpecIssueKind::DuplicateName(field.name.clone()));
+            }
+            if let Some(other) = numbers.insert(field.number, &field.name) {
+                self.report(
+                    &location,
+                    SpecIssueKind::DuplicateFieldNumber {
+                        number: field.number,
+                        other: other.clone(),
+                    },
+                );
+            }
+            if !field.values.is_empty() && !field.field_type.supports_enum_values() {
+                self.report(
+                    &location,
+                    SpecIssueKind::UnsupportedEnumType(field.field_type),
+                );
+            }
+            self.validate_values(field, &location);
+        }
+    }
+
+    fn validate_values(&mut self, field: &FieldDef, location: &str) {
+        let mut values = HashMap::new();
+        let mut variants = HashMap::new();
+
+        for value in &field.values {
+            let location = format!("{}/value[@enum='{}']", location, value.value);
+
+            if values.insert(&value.value, value).is_some() {
+                self.report(
+                    &location,
+                    SpecIssueKind::DuplicateEnumValue(value.value.clone()),
+                );
+            }
+            if value.description.is_empty() {
+                self.report(&location, SpecIssueKind::EmptyEnumDescription);
+                continue;
+            }
+            let variant = value.as_rust_desc();
+            if let Some(other) = variants.insert(variant.clone(), &value.value) {
+                self.report(
+                    &location,
+                    SpecIssueKind::DuplicateEnumVariant {
+                        variant,
+                        other: other.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    fn validate_refs(&mut self, refs: &[Reference], location: &str) {
+        let mut names = Vec::new();
+
+        for item in refs {
+            let location = match item {
+                Reference::FieldRef(x) => format!("{}/field[@name='{}']", location, x.name),
+                Reference::ComponentRef(x) => format!("{}/component[@name='{}']", location, x.name),
+                Reference::GroupRef(x) => format!("{}/group[@name='{}']", location, x.name),
+            };
+
+            if names.contains(&item.name()) {
+                self.report(
+                    &location,
+                    SpecIssueKind::DuplicateName(item.name().to_string()),
+                );
+            }
+            names.push(item.name());
+
+            match item {
+                Reference::FieldRef(x) => {
+                    if !self.fields.contains_key(x.name.as_str()) {
+                        self.report(&location, SpecIssueKind::UnknownField(x.name.clone()));
+                    }
+                }
+                Reference::ComponentRef(x) => {
+                    if !self.components.contains_key(x.name.as_str()) {
+                        self.report(&location, SpecIssueKind::UnknownComponent(x.name.clone()));
+                    }
+                }
+                Reference::GroupRef(x) => {
+                    if !self.fields.contains_key(x.name.as_str()) {
+                        self.report(&location, SpecIssueKind::UnknownField(x.name.clone()));
+                    }
+                    if x.refs.is_empty() {
+                        self.report(&location, SpecIssueKind::EmptyGroup);
+                    }
+                    self.validate_refs(&x.refs, &location);
+                }
+            }
+        }
+    }
+
+    /// Find component references loop starting from last component of `path`
+    fn find_component_loop(&self, refs: &[Reference], path: &mut Vec<String>) -> bool {
+        for item in refs {
+            let found = match item {
+                Reference::ComponentRef(x) if path[0] == x.name => {
+                    path.push(x.name.clone());
+                    true
+                }
+                Reference::ComponentRef(x) if !path.contains(&x.name) => {
+                    match self.components.get(x.name.as_str()) {
+                        Some(component) => {
+                            path.push(x.name.clone());
+                            self.find_component_loop(&component.refs, path) || {
+                                path.pop();
+                                false
+                            }
+                        }
+                        None => false,
+                    }
+                }
+                Reference::GroupRef(x) => self.find_component_loop(&x.refs, path),
+                _ => false,
+            };
+
+            if found {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn validate_components(&mut self, spec: &'a ComponentSpec) {
+        for component in &spec.items {
+            let location = format!("components/component[@name='{}']", component.name);
+            self.validate_refs(&component.refs, &location);
+
+            let mut path = vec![component.name.clone()];
+            if self.find_component_loop(&component.refs, &mut path) {
+                self.report(&location, SpecIssueKind::RecursiveComponent(path));
+            }
+        }
+    }
+
+    fn validate_messages(&mut self, spec: &MessagesSpec) {
+        let mut names = HashMap::new();
+        let mut msg_types = HashMap::new();
+
+        for message in &spec.items {
+            let location = format!("messages/message[@name='{}']", message.name);
+
+            if names.insert(&message.name, message).is_some() {
+                self.report(
+                    &location,
+                    SpecIssueKind::DuplicateName(message.name.clone()),
+                );
+            }
+            // Scenarios share MsgType of base message
+            let msg_type_owner = if message.is_base_scenario() {
+                msg_types.insert(&message.msgtype, &message.name)
+            } else {
+                None
+            };
+            if let Some(other) = msg_type_owner {
+                self.report(
+                    &location,
+                    SpecIssueKind::DuplicateMsgType {
+                        msg_type: message.msgtype.clone(),
+                        other: other.clone(),
+                    },
+                );
+            }
+            self.validate_refs(&message.refs, &location);
+        }
+    }
+}
+
+impl FixSpec {
+    /// Check dictionary can be used to generate code, and report every problem found
+    pub fn validate(&self) -> Result<(), SpecValidationError> {
+        let mut validator = SpecValidator {
+            fields: HashMap::new(),
+            components: HashMap::new(),
+            issues: vec![],
+        };
+
+        validator.validate_fields(&self.field);
+
+        if let Some(component) = &self.component {
+            for item in &component.items {
+                if validator.components.insert(&item.name, item).is_some() {
+                    validator.report(
+                        &format!("components/component[@name='{}']", item.name),
+                        SpecIssueKind::DuplicateName(item.name.clone()),
+                    );
+                }
+            }
+        }
+
+        if let Some(header) = &self.header {
+            validator.validate_refs(&header.refs, "header");
+        }
+        if let Some(trailer) = &self.trailer {
+            validator.validate_refs(&trailer.refs, "trailer");
+        }
+        if let Some(component) = &self.component {
+            validator.validate_components(component);
+        }
+        validator.validate_messages(&self.message);
+
+        if validator.issues.is_empty() {
+            Ok(())
+        } else {
+            Err(SpecValidationError {
+                issues: validator.issues,
+            })
+        }
+    }
+}
+
+/// Parse QuickFIX dictionary or Orchestra repository file
 pub fn parse<P: AsRef<Path>>(path: P) -> anyhow::Result<FixSpec> {
-    let file = fs::File::open(path)?;
-    let buf = BufReader::new(file);
-    let spec = quick_xml::de::from_reader(buf)?;
+    parse_str(&fs::read_to_string(path)?)
+}
+
+/// Parse QuickFIX dictionary or Orchestra repository XML, detected from root element
+pub fn parse_str(xml: &str) -> anyhow::Result<FixSpec> {
+    if orchestra::is_orchestra(xml) {
+        return orchestra::parse_str(xml);
+    }
+
+    let spec = quick_xml::de::from_str(xml)?;
     Ok(spec)
 }
 
+/// Dictionary to generate, with overlays to apply on it
+#[derive(Debug)]
+struct BuilderSource<P> {
+    path: P,
+    overlays: Vec<P>,
+    transport: Option<P>,
+}
+
+impl<P> BuilderSource<P>
+where
+    P: AsRef<Path>,
+{
+    /// Generated files are named after last overlay
+    fn src_filename(&self) -> &P {
+        self.overlays.last().unwrap_or(&self.path)
+    }
+
+    fn parse(&self) -> anyhow::Result<FixSpec> {
+        let mut spec = parse(&self.path)?;
+        for overlay in &self.overlays {
+            let overlay_spec = parse(overlay)?;
+            spec.merge(overlay_spec).map_err(|err| {
+                anyhow::anyhow!("cannot apply {}: {}", overlay.as_ref().display(), err)
+            })?;
+        }
+        if let Some(transport) = &self.transport {
+            spec.use_transport(&parse(transport)?);
+        }
+        Ok(spec)
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Builder<P>
 where
     P: AsRef<Path>,
 {
-    paths: Vec<P>,
-    enable_rustfmt: bool,
+    sources: Vec<BuilderSource<P>>,
+    options: GeneratorOptions,
 }
 
 impl<P> Builder<P>
@@ -923,26 +2476,543 @@ where
 {
     pub fn new() -> Self {
         Self {
-            paths: vec![],
-            enable_rustfmt: false,
+            sources: vec![],
+            options: GeneratorOptions::default(),
         }
     }
 
     pub fn add_path(mut self, path: P) -> Self {
-        self.paths.push(path);
+        self.sources.push(BuilderSource {
+            path,
+            overlays: vec![],
+            transport: None,
+        });
+        self
+    }
+
+    /// Merge overlay dictionaries into base one, and generate a single module named after last overlay
+    pub fn add_overlays(mut self, base: P, overlays: Vec<P>) -> Self {
+        self.sources.push(BuilderSource {
+            path: base,
+            overlays,
+            transport: None,
+        });
+        self
+    }
+
+    /// Generate application dictionary (ie: FIX 5.0) using header and trailer of transport dictionary (ie: FIXT 1.1)
+    pub fn add_application(mut self, transport: P, application: P) -> Self {
+        self.sources.push(BuilderSource {
+            path: application,
+            overlays: vec![],
+            transport: Some(transport),
+        });
         self
     }
 
-    pub fn enable_rustfmt(mut self, value: bool) -> Self {
-        self.enable_rustfmt = value;
+    /// Decode enum values missing from dictionary instead of failing
+    pub fn enable_unknown_enum_values(mut self, value: bool) -> Self {
+        self.options.enable_unknown_enum_values = value;
         self
     }
 
     pub fn build(&self, out_dir: P) -> anyhow::Result<()> {
-        self.paths.iter().try_for_each(|file| {
-            let spec = parse(file)?;
-            spec.generate_specfile(&out_dir, &file, self.enable_rustfmt)?;
+        self.sources.iter().try_for_each(|source| {
+            let spec = source.parse()?;
+            spec.validate().with_context(|| {
+                format!(
+                    "cannot generate {}",
+                    source.src_filename().as_ref().display()
+                )
+            })?;
+            spec.generate_specfile(&out_dir, source.src_filename(), &self.options)?;
             Ok(())
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(xml: &str) -> Vec<String> {
+        let spec = parse_str(xml).unwrap();
+        match spec.validate() {
+            Ok(()) => vec![],
+            Err(err) => err.issues.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate_valid() {
+        assert_eq!(
+            issues(
+                "<fix major='4' minor='4' servicepack='0'>
+                 <messages>
+                  <message name='Heartbeat' msgtype='0' msgcat='admin'>
+                   <field name='TestReqID' required='N' />
+                  </message>
+                 </messages>
+                 <fields>
+                  <field number='112' name='TestReqID' type='STRING' />
+                 </fields>
+                </fix>"
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_validate_references() {
+        assert_eq!(
+            issues(
+                "<fix major='4' minor='4' servicepack='0'>
+                 <header>
+                  <field name='MsgType' required='Y' />
+                 </header>
+                 <messages>
+                  <message name='Heartbeat' msgtype='0' msgcat='admin'>
+                   <component name='Parties' required='N' />
+                   <group name='NoPartyIDs' required='N'>
+                    <field name='PartyID' required='N' />
+                    <field name='PartyID' required='N' />
+                   </group>
+                  </message>
+                  <message name='TestRequest' msgtype='0' msgcat='admin'>
+                  </message>
+                 </messages>
+                 <fields>
+                  <field number='448' name='PartyID' type='STRING' />
+                  <field number='448' name='NoPartyIDs' type='NUMINGROUP' />
+                 </fields>
+                </fix>"
+            ),
+            vec![
+                "fields/field[@name='NoPartyIDs']: duplicate field ID 448, already used by field PartyID",
+                "header/field[@name='MsgType']: unknown field reference: MsgType",
+                "messages/message[@name='Heartbeat']/component[@name='Parties']: unknown component reference: Parties",
+                "messages/message[@name='Heartbeat']/group[@name='NoPartyIDs']/field[@name='PartyID']: duplicate name: PartyID",
+                "messages/message[@name='TestRequest']: duplicate MsgType \"0\", already used by message Heartbeat",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_enum_values() {
+        assert_eq!(
+            issues(
+                "<fix major='4' minor='4' servicepack='0'>
+                 <fields>
+                  <field number='54' name='Side' type='CHAR'>
+                   <value enum='1' description='BUY' />
+                   <value enum='1' description='SELL' />
+                   <value enum='3' description='' />
+                   <value enum='4' description='buy' />
+                  </field>
+                  <field number='44' name='Price' type='PRICE'>
+                   <value enum='0' description='ZERO' />
+                  </field>
+                 </fields>
+                </fix>"
+            ),
+            vec![
+                "fields/field[@name='Side']/value[@enum='1']: duplicate enum value \"1\"",
+                "fields/field[@name='Side']/value[@enum='3']: empty enum description",
+                "fields/field[@name='Side']/value[@enum='4']: enum description generates variant Buy, already used by value \"1\"",
+                "fields/field[@name='Price']: enum values are not supported on Price fields",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_recursive_components() {
+        assert_eq!(
+            issues(
+                "<fix major='4' minor='4' servicepack='0'>
+                 <components>
+                  <component name='Instrument'>
+                   <group name='NoLegs' required='N'>
+                    <component name='InstrumentLeg' required='N' />
+                   </group>
+                  </component>
+                  <component name='InstrumentLeg'>
+                   <component name='Instrument' required='N' />
+                  </component>
+                 </components>
+                 <fields>
+                  <field number='555' name='NoLegs' type='NUMINGROUP' />
+                 </fields>
+                </fix>"
+            ),
+            vec![
+                "components/component[@name='Instrument']: recursive component: Instrument -> InstrumentLeg -> Instrument",
+                "components/component[@name='InstrumentLeg']: recursive component: InstrumentLeg -> Instrument -> InstrumentLeg",
+            ]
+        );
+    }
+
+    fn ref_names(refs: &[Reference]) -> Vec<&str> {
+        refs.iter().map(|x| x.name()).collect()
+    }
+
+    fn merge_err(base: &str, overlay: &str) -> String {
+        let mut spec = parse_str(base).unwrap();
+        spec.merge(parse_str(overlay).unwrap())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_merge_extend_and_replace() {
+        let mut spec = parse_str(
+            "<fix major='4' minor='4' servicepack='0'>
+             <header>
+              <field name='MsgType' required='Y' />
+             </header>
+             <messages>
+              <message name='Heartbeat' msgtype='0' msgcat='admin'>
+               <field name='TestReqID' required='N' />
+              </message>
+              <message name='News' msgtype='B' msgcat='app'>
+               <field name='Headline' required='Y' />
+               <field name='Text' required='N' />
+              </message>
+             </messages>
+             <components>
+              <component name='Instrument'>
+               <field name='Symbol' required='Y' />
+              </component>
+              <component name='Parties'>
+               <field name='PartyID' required='N' />
+              </component>
+             </components>
+             <fields>
+              <field number='35' name='MsgType' type='STRING'>
+               <value enum='0' description='HEARTBEAT' />
+               <value enum='B' description='NEWS' />
+              </field>
+              <field number='54' name='Side' type='CHAR'>
+               <value enum='1' description='BUY' />
+               <value enum='2' description='SELL' />
+              </field>
+             </fields>
+            </fix>",
+        )
+        .unwrap();
+        spec.merge(
+            parse_str(
+                "<fix major='4' minor='4' servicepack='0'>
+                 <header overlay='replace'>
+                  <field name='BeginString' required='Y' />
+                 </header>
+                 <messages>
+                  <message name='Heartbeat' msgtype='0' msgcat='admin'>
+                   <field name='TestReqID' required='Y' />
+                   <field name='UserData' required='N' />
+                  </message>
+                  <message name='News' msgtype='B' msgcat='app' overlay='replace'>
+                   <field name='UserData' required='N' />
+                  </message>
+                  <message name='ZeOrder' msgtype='ZZ' msgcat='app' />
+                 </messages>
+                 <components>
+                  <component name='Instrument'>
+                   <field name='SecurityID' required='N' />
+                  </component>
+                  <component name='Parties' overlay='replace'>
+                   <field name='PartyRole' required='N' />
+                  </component>
+                  <component name='ZeComponent'>
+                   <field name='UserData' required='N' />
+                  </component>
+                 </components>
+                 <fields>
+                  <field number='54' name='Side' type='CHAR'>
+                   <value enum='2' description='SELL_SIDE' />
+                   <value enum='Z' description='ZE_SIDE' />
+                  </field>
+                  <field number='35' name='MsgType' type='STRING' overlay='replace'>
+                   <value enum='ZZ' description='ZE_ORDER' />
+                  </field>
+                  <field number='16249' name='UserData' type='STRING' />
+                 </fields>
+                </fix>",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        // Replace
+        assert_eq!(
+            ref_names(&spec.header.as_ref().unwrap().refs),
+            vec!["BeginString"]
+        );
+        // Extend: same name references are replaced in place, new ones appended
+        let messages = &spec.message.items;
+        assert_eq!(
+            messages.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(),
+            vec!["Heartbeat", "News", "ZeOrder"]
+        );
+        assert_eq!(ref_names(&messages[0].refs), vec!["TestReqID", "UserData"]);
+        assert!(matches!(
+            &messages[0].refs[0],
+            Reference::FieldRef(x) if x.required == Required::Y
+        ));
+        assert_eq!(ref_names(&messages[1].refs), vec!["UserData"]);
+
+        let components = &spec.component.as_ref().unwrap().items;
+        assert_eq!(
+            components
+                .iter()
+                .map(|x| (x.name.as_str(), ref_names(&x.refs)))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Instrument", vec!["Symbol", "SecurityID"]),
+                ("Parties", vec!["PartyRole"]),
+                ("ZeComponent", vec!["UserData"]),
+            ]
+        );
+
+        let fields = &spec.field.items;
+        assert_eq!(
+            fields
+                .iter()
+                .map(|x| (
+                    x.name.as_str(),
+                    x.values
+                        .iter()
+                        .map(|v| (v.value.as_str(), v.description.as_str()))
+                        .collect::<Vec<_>>()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("MsgType", vec![("ZZ", "ZE_ORDER")]),
+                (
+                    "Side",
+                    vec![("1", "BUY"), ("2", "SELL_SIDE"), ("Z", "ZE_SIDE")]
+                ),
+                ("UserData", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_duplicate_field_id() {
+        let base = "<fix major='4' minor='4' servicepack='0'>
+                     <fields>
+                      <field number='54' name='Side' type='CHAR' />
+                     </fields>
+                    </fix>";
+
+        assert_eq!(
+            merge_err(
+                base,
+                "<fix major='4' minor='4' servicepack='0'>
+                 <fields>
+                  <field number='54' name='ZeSide' type='CHAR' />
+                 </fields>
+                </fix>"
+            ),
+            "field ZeSide uses ID 54 already used by field Side"
+        );
+        assert_eq!(
+            merge_err(
+                base,
+                "<fix major='4' minor='4' servicepack='0'>
+                 <fields>
+                  <field number='16254' name='Side' type='CHAR' />
+                 </fields>
+                </fix>"
+            ),
+            "field Side cannot change ID or type without overlay='replace'"
+        );
+
+        // Replace can change ID and type
+        let mut spec = parse_str(base).unwrap();
+        spec.merge(
+            parse_str(
+                "<fix major='4' minor='4' servicepack='0'>
+                 <fields>
+                  <field number='16254' name='Side' type='STRING' overlay='replace' />
+                 </fields>
+                </fix>",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(spec.field.items[0].number, 16254);
+        assert_eq!(spec.field.items[0].field_type, FieldType::String);
+    }
+
+    #[test]
+    fn test_merge_duplicate_msgtype() {
+        let base = "<fix major='4' minor='4' servicepack='0'>
+                     <messages>
+                      <message name='News' msgtype='B' msgcat='app' />
+                     </messages>
+                    </fix>";
+
+        assert_eq!(
+            merge_err(
+                base,
+                "<fix major='4' minor='4' servicepack='0'>
+                 <messages>
+                  <message name='ZeNews' msgtype='B' msgcat='app' />
+                 </messages>
+                </fix>"
+            ),
+            "message ZeNews uses MsgType \"B\" already used by message News"
+        );
+        assert_eq!(
+            merge_err(
+                base,
+                "<fix major='4' minor='4' servicepack='0'>
+                 <messages>
+                  <message name='News' msgtype='ZB' msgcat='app' />
+                 </messages>
+                </fix>"
+            ),
+            "message News cannot change MsgType from \"B\" to \"ZB\" without overlay='replace'"
+        );
+
+        let mut spec = parse_str(base).unwrap();
+        spec.merge(
+            parse_str(
+                "<fix major='4' minor='4' servicepack='0'>
+                 <messages>
+                  <message name='News' msgtype='ZB' msgcat='app' overlay='replace' />
+                 </messages>
+                </fix>",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(spec.message.items[0].msgtype, "ZB");
+    }
+
+    #[test]
+    fn test_generate_invalid() {
+        let spec = parse_str(
+            "<fix major='4' minor='4' servicepack='0'>
+             <messages>
+              <message name='Heartbeat' msgtype='0' msgcat='admin'>
+               <component name='Parties' required='N' />
+              </message>
+             </messages>
+            </fix>",
+        )
+        .unwrap();
+        let err = spec
+            .generate_code(&GeneratorOptions::default())
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            err.downcast_ref::<SpecValidationError>()
+                .map(|x| x.issues.len()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_use_transport() {
+        let transport = parse_str(
+            "<fix major='1' minor='1' servicepack='0'>
+             <header>
+              <field name='MsgType' required='Y' />
+              <field name='ApplVerID' required='N' />
+             </header>
+             <trailer>
+              <field name='CheckSum' required='Y' />
+             </trailer>
+             <fields>
+              <field number='35' name='MsgType' type='STRING' />
+              <field number='1128' name='ApplVerID' type='STRING' />
+              <field number='10' name='CheckSum' type='STRING' />
+             </fields>
+            </fix>",
+        )
+        .unwrap();
+        let mut application = parse_str(
+            "<fix major='5' minor='0' servicepack='2'>
+             <header />
+             <trailer />
+             <messages>
+              <message name='News' msgtype='B' msgcat='app'>
+               <field name='Headline' required='Y' />
+              </message>
+             </messages>
+             <fields>
+              <field number='35' name='MsgType' type='STRING'>
+               <value enum='B' description='NEWS' />
+              </field>
+              <field number='148' name='Headline' type='STRING' />
+             </fields>
+            </fix>",
+        )
+        .unwrap();
+
+        assert_eq!(transport.appl_ver_id(), None);
+        assert_eq!(application.appl_ver_id(), Some("9"));
+
+        application.use_transport(&transport);
+        assert_eq!(application.header, transport.header);
+        assert_eq!(application.trailer, transport.trailer);
+        assert_eq!(
+            application
+                .field
+                .items
+                .iter()
+                .map(|x| (x.name.as_str(), x.values.len()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("MsgType", 1),
+                ("Headline", 0),
+                ("ApplVerID", 0),
+                ("CheckSum", 0)
+            ]
+        );
+        assert_eq!(application.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_generate_docs() {
+        let spec = parse_str(
+            "<fix major='4' minor='4' servicepack='0'>
+             <header>
+              <field name='MsgType' required='Y' />
+             </header>
+             <messages>
+              <message name='NewOrderSingle' msgtype='D' msgcat='app' doc='Order to buy or sell'>
+               <field name='Side' required='Y' />
+               <field name='Text' required='N' />
+              </message>
+             </messages>
+             <fields>
+              <field number='35' name='MsgType' type='STRING'>
+               <value enum='D' description='ORDER_SINGLE' />
+              </field>
+              <field number='54' name='Side' type='CHAR'>
+               <value enum='1' description='BUY' doc='Buy side' />
+              </field>
+              <field number='58' name='Text' type='STRING' />
+             </fields>
+            </fix>",
+        )
+        .unwrap();
+        let code = spec.generate_code(&GeneratorOptions::default()).unwrap();
+        let fields = format_code(code.fields).unwrap();
+        let messages = format_code(code.messages).unwrap();
+
+        assert!(fields
+            .contains("/// MsgType (35), FIX type `STRING`\n///\n/// Used by: StandardHeader\n"));
+        assert!(
+            fields.contains("/// Side (54), FIX type `CHAR`\n///\n/// Used by: NewOrderSingle\n")
+        );
+        assert!(fields.contains("    /// `1`: BUY\n    ///\n    /// Buy side\n    Buy,"));
+        assert!(messages.contains(
+            "/// NewOrderSingle application message, MsgType `D`\n///\n\
+             /// Order to buy or sell\n///\n/// Required fields: Side\n"
+        ));
+    }
+}
@@ -0,0 +1,745 @@
+//! FIX Orchestra repository parser.
+//!
+//! Orchestra files published by FIX Trading Community (FIX Latest, and older versions converted
+//! from the Unified Repository) are mapped to the same model as QuickFIX dictionaries:
+//!
+//! - code sets become field values,
+//! - datatypes are resolved to known field types, using their `baseType` when needed,
+//! - StandardHeader / StandardTrailer components become message header / trailer,
+//! - groups are inlined where they are referenced, like in QuickFIX dictionaries,
+//! - annotations (synopsis) and pedigree are kept as documentation,
+//! - non base scenarios of messages and components are kept as extra items named
+//!   `{name}{Scenario}` (ie: `NewOrderSingleDma`).
+//!
+//! Fields are always mapped to their base scenario, as a tag has a single definition per dictionary.
+
+use anyhow::Context;
+use convert_case::{Case, Casing};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::fs;
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::{
+    Component, ComponentRef, ComponentSpec, FieldDef, FieldRef, FieldSpec, FieldType, FieldValue,
+    FixSpec, GroupRef, HeaderSpec, Message, MessageCategory, MessagesSpec, OverlayMode, Reference,
+    Required, TrailerSpec,
+};
+
+const BASE_SCENARIO: &str = "base";
+const HEADER_NAME: &str = "StandardHeader";
+const TRAILER_NAME: &str = "StandardTrailer";
+
+// =====================================
+// Generic XML tree
+
+/// XML element, with namespace prefixes removed from element and attribute names
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: HashMap<String, String>,
+    children: Vec<Element>,
+    text: String,
+}
+
+fn local_name(name: &[u8]) -> String {
+    let name = String::from_utf8_lossy(name);
+    match name.split_once(':') {
+        Some((_prefix, local)) => local.to_string(),
+        None => name.into_owned(),
+    }
+}
+
+impl Element {
+    fn from_start<B: BufRead>(start: &BytesStart, reader: &Reader<B>) -> anyhow::Result<Self> {
+        let mut attributes = HashMap::new();
+        for attribute in start.attributes() {
+            let attribute = attribute?;
+            if attribute.key == b"xmlns" || attribute.key.starts_with(b"xmlns:") {
+                continue;
+            }
+            attributes.insert(
+                local_name(attribute.key),
+                attribute.unescape_and_decode_value(reader)?,
+            );
+        }
+
+        Ok(Self {
+            name: local_name(start.name()),
+            attributes,
+            ..Default::default()
+        })
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    fn required_attr(&self, name: &str) -> anyhow::Result<&str> {
+        self.attr(name)
+            .with_context(|| format!("{} element has no {} attribute", self.name, name))
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|x| x.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |x| x.name == name)
+    }
+
+    /// Children of a container element (ie: every `field` of `fields`)
+    fn items<'a>(&'a self, container: &str, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.child(container)
+            .into_iter()
+            .flat_map(move |x| x.children(name))
+    }
+
+    fn scenario(&self) -> &str {
+        self.attr("scenario").unwrap_or(BASE_SCENARIO)
+    }
+
+    /// Annotation synopsis (or first documentation), followed by pedigree
+    fn documentation(&self) -> Option<String> {
+        let docs: Vec<_> = self
+            .child("annotation")
+            .into_iter()
+            .flat_map(|x| x.children("documentation"))
+            .collect();
+        let synopsis = docs
+            .iter()
+            .find(|x| x.attr("purpose") == Some("SYNOPSIS"))
+            .or_else(|| docs.first())
+            .map(|x| x.text.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|x| !x.is_empty());
+
+        let mut pedigree = Vec::new();
+        if let Some(added) = self.attr("added") {
+            match self.attr("addedEP") {
+                Some(ep) => pedigree.push(format!("Added in {} EP{}.", added, ep)),
+                None => pedigree.push(format!("Added in {}.", added)),
+            }
+        }
+        if let Some(deprecated) = self.attr("deprecated") {
+            pedigree.push(format!("Deprecated in {}.", deprecated));
+        }
+        let pedigree = Some(pedigree.join(" ")).filter(|x| !x.is_empty());
+
+        match (synopsis, pedigree) {
+            (Some(synopsis), Some(pedigree)) => Some(format!("{}\n\n{}", synopsis, pedigree)),
+            (synopsis, pedigree) => synopsis.or(pedigree),
+        }
+    }
+}
+
+fn read_document(xml: &str) -> anyhow::Result<Element> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+
+    let mut buf = Vec::new();
+    let mut stack: Vec<Element> = Vec::new();
+
+    loop {
+        let element = match reader.read_event(&mut buf)? {
+            Event::Start(start) => {
+                stack.push(Element::from_start(&start, &reader)?);
+                None
+            }
+            Event::Empty(start) => Some(Element::from_start(&start, &reader)?),
+            Event::End(_) => stack.pop(),
+            Event::Text(text) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.text.push_str(&text.unescape_and_decode(&reader)?);
+                }
+                None
+            }
+            Event::CData(text) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.text.push_str(reader.decode(&text)?);
+                }
+                None
+            }
+            Event::Eof => anyhow::bail!("unexpected end of document"),
+            _ => None,
+        };
+
+        if let Some(element) = element {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(element),
+                None => return Ok(element),
+            }
+        }
+        buf.clear();
+    }
+}
+
+/// Check if document root is an Orchestra repository
+pub(crate) fn is_orchestra(xml: &str) -> bool {
+    let mut reader = Reader::from_str(xml);
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(start)) | Ok(Event::Empty(start)) => {
+                return local_name(start.name()) == "repository";
+            }
+            Ok(Event::Eof) | Err(_) => return false,
+            _ => buf.clear(),
+        }
+    }
+}
+
+// =====================================
+// Orchestra to FIX spec
+
+/// Name of a scenario item in generated code
+fn scenario_name(name: &str, scenario: &str) -> String {
+    if scenario == BASE_SCENARIO {
+        name.to_string()
+    } else {
+        format!("{}{}", name, scenario.to_case(Case::UpperCamel))
+    }
+}
+
+/// Parse version like "FIX.4.4", "FIX.5.0SP2_EP253" or "FIX.Latest"
+fn parse_version(version: &str) -> anyhow::Result<(u8, u8, u8)> {
+    let version = version.split('_').next().unwrap_or_default();
+    let version = version.strip_prefix("FIX.").unwrap_or(version);
+
+    // FIX Latest extends FIX 5.0SP2 and uses the same session layer
+    if version == "Latest" {
+        return Ok((5, 0, 2));
+    }
+
+    let (version, servicepack) = match version.split_once("SP") {
+        Some((version, servicepack)) => (version, servicepack.parse()?),
+        None => (version, 0),
+    };
+    let (major, minor) = version
+        .split_once('.')
+        .with_context(|| format!("invalid FIX version: {}", version))?;
+
+    Ok((major.parse()?, minor.parse()?, servicepack))
+}
+
+/// Field type of Orchestra datatypes also known by QuickFIX dictionaries
+fn known_field_type(datatype: &str) -> Option<FieldType> {
+    match datatype {
+        "int" | "TagNum" | "Reserved100Plus" | "Reserved1000Plus" | "Reserved4000Plus" => {
+            Some(FieldType::Int)
+        }
+        "Length" => Some(FieldType::Length),
+        "SeqNum" => Some(FieldType::Seqnum),
+        "NumInGroup" => Some(FieldType::NumInGroup),
+        "DayOfMonth" => Some(FieldType::DayOfMonth),
+        "float" => Some(FieldType::Float),
+        "Qty" => Some(FieldType::Quantity),
+        "Price" => Some(FieldType::Price),
+        "PriceOffset" => Some(FieldType::PriceOffset),
+        "Amt" => Some(FieldType::Amount),
+        "Percentage" => Some(FieldType::Percentage),
+        "char" => Some(FieldType::Char),
+        "Boolean" => Some(FieldType::Boolean),
+        "String" | "Language" | "Pattern" | "Tenor" | "XID" | "XIDREF" | "LocalMktTime"
+        | "TZTimeOnly" | "TZTimestamp" => Some(FieldType::String),
+        "MultipleValueString" => Some(FieldType::MultipleValueString),
+        "MultipleCharValue" => Some(FieldType::MultipleCharValue),
+        "MultipleStringValue" => Some(FieldType::MultipleStringValue),
+        "Country" => Some(FieldType::Country),
+        "Currency" => Some(FieldType::Currency),
+        "Exchange" => Some(FieldType::Exchange),
+        "MonthYear" => Some(FieldType::MonthYear),
+        "UTCTimestamp" => Some(FieldType::UtcTimestamp),
+        "UTCTimeOnly" => Some(FieldType::UtcTimeOnly),
+        "UTCDate" => Some(FieldType::UtcDate),
+        "UTCDateOnly" => Some(FieldType::UtcDateOnly),
+        "date" => Some(FieldType::Date),
+        "time" => Some(FieldType::Time),
+        "LocalMktDate" => Some(FieldType::LocalMarketDate),
+        "data" | "XMLData" => Some(FieldType::Data),
+        _ => None,
+    }
+}
+
+/// Orchestra elements indexed by name / ID
+struct Repository<'a> {
+    datatypes: HashMap<&'a str, &'a Element>,
+    code_sets: HashMap<(&'a str, &'a str), &'a Element>,
+    fields: HashMap<&'a str, &'a Element>,
+    components: HashMap<(&'a str, &'a str), &'a Element>,
+    groups: HashMap<(&'a str, &'a str), &'a Element>,
+}
+
+impl<'a> Repository<'a> {
+    fn new(root: &'a Element) -> anyhow::Result<Self> {
+        let by_name = |container, name| -> anyhow::Result<HashMap<_, _>> {
+            root.items(container, name)
+                .map(|x| Ok((x.required_attr("name")?, x)))
+                .collect()
+        };
+        let by_scenario = |container, name, key| -> anyhow::Result<HashMap<_, _>> {
+            root.items(container, name)
+                .map(|x| Ok(((x.required_attr(key)?, x.scenario()), x)))
+                .collect()
+        };
+
+        Ok(Self {
+            datatypes: by_name("datatypes", "datatype")?,
+            code_sets: by_scenario("codeSets", "codeSet", "name")?,
+            fields: root
+                .items("fields", "field")
+                .filter(|x| x.scenario() == BASE_SCENARIO)
+                .map(|x| Ok((x.required_attr("id")?, x)))
+                .collect::<anyhow::Result<_>>()?,
+            components: by_scenario("components", "component", "id")?,
+            groups: by_scenario("groups", "group", "id")?,
+        })
+    }
+
+    fn field(&self, id: &str) -> anyhow::Result<&'a Element> {
+        self.fields
+            .get(id)
+            .copied()
+            .with_context(|| format!("unknown field ID {}", id))
+    }
+
+    fn field_name(&self, id: &str) -> anyhow::Result<String> {
+        Ok(self.field(id)?.required_attr("name")?.to_string())
+    }
+
+    fn datatype(&self, name: &str) -> anyhow::Result<FieldType> {
+        let mut datatype = name;
+        // Resolve user defined datatypes, with a depth limit in case of loop
+        for _ in 0..8 {
+            if let Some(field_type) = known_field_type(datatype) {
+                return Ok(field_type);
+            }
+            match self
+                .datatypes
+                .get(datatype)
+                .and_then(|x| x.attr("baseType"))
+            {
+                Some(base_type) => datatype = base_type,
+                None => break,
+            }
+        }
+        anyhow::bail!("unsupported datatype: {}", name)
+    }
+
+    fn field_def(&self, field: &Element) -> anyhow::Result<FieldDef> {
+        let name = field.required_attr("name")?;
+        let type_name = field.required_attr("type")?;
+        let code_set = self
+            .code_sets
+            .get(&(type_name, field.scenario()))
+            .or_else(|| self.code_sets.get(&(type_name, BASE_SCENARIO)));
+
+        let (field_type, values) = match code_set {
+            Some(code_set) => (
+                self.datatype(code_set.required_attr("type")?)?,
+                code_set
+                    .children("code")
+                    .map(|code| {
+                        Ok(FieldValue {
+                            value: code.required_attr("value")?.to_string(),
+                            description: code.required_attr("name")?.to_string(),
+                            doc: code.documentation(),
+                        })
+                    })
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            None => (self.datatype(type_name)?, vec![]),
+        };
+
+        Ok(FieldDef {
+            name: name.to_string(),
+            number: field
+                .required_attr("id")?
+                .parse()
+                .with_context(|| format!("invalid ID of field {}", name))?,
+            field_type,
+            overlay: OverlayMode::default(),
+            doc: field.documentation(),
+            values,
+        })
+    }
+
+    fn scenario_item(
+        items: &HashMap<(&str, &str), &'a Element>,
+        reference: &Element,
+    ) -> anyhow::Result<&'a Element> {
+        let id = reference.required_attr("id")?;
+        items
+            .get(&(id, reference.scenario()))
+            .copied()
+            .with_context(|| {
+                format!(
+                    "unknown {} ID {} (scenario {})",
+                    reference.name,
+                    id,
+                    reference.scenario()
+                )
+            })
+    }
+
+    fn refs(&self, parent: &Element) -> anyhow::Result<Vec<Reference>> {
+        let mut refs = Vec::new();
+
+        for reference in &parent.children {
+            let required = match reference.attr("presence") {
+                Some("required") | Some("constant") => Required::Y,
+                Some("forbidden") => continue,
+                _ => Required::N,
+            };
+
+            match reference.name.as_str() {
+                "fieldRef" => refs.push(Reference::FieldRef(FieldRef {
+                    name: self.field_name(reference.required_attr("id")?)?,
+                    required,
+                })),
+                "componentRef" => {
+                    let component = Self::scenario_item(&self.components, reference)?;
+                    let name = component.required_attr("name")?;
+                    // Header and trailer are implicit in generated messages
+                    if name == HEADER_NAME || name == TRAILER_NAME {
+                        continue;
+                    }
+                    refs.push(Reference::ComponentRef(ComponentRef {
+                        name: scenario_name(name, component.scenario()),
+                        required,
+                    }));
+                }
+                "groupRef" => {
+                    let group = Self::scenario_item(&self.groups, reference)?;
+                    let num_in_group = group.child("numInGroup").with_context(|| {
+                        format!("group {:?} has no numInGroup", group.attr("name"))
+                    })?;
+                    refs.push(Reference::GroupRef(GroupRef {
+                        name: self.field_name(num_in_group.required_attr("id")?)?,
+                        required,
+                        refs: self.refs(group)?,
+                    }));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(refs)
+    }
+
+    fn component(&self, name: &str) -> anyhow::Result<Option<Vec<Reference>>> {
+        self.components
+            .values()
+            .find(|x| x.attr("name") == Some(name) && x.scenario() == BASE_SCENARIO)
+            .map(|x| self.refs(x))
+            .transpose()
+    }
+}
+
+fn spec_from_repository(root: &Element) -> anyhow::Result<FixSpec> {
+    if root.name != "repository" {
+        anyhow::bail!("expected Orchestra repository, found {} element", root.name);
+    }
+
+    let version = root
+        .attr("version")
+        .or_else(|| root.attr("name"))
+        .context("repository has no version")?;
+    let (major, minor, servicepack) = parse_version(version)?;
+    let repository = Repository::new(root)?;
+
+    let fields = root
+        .items("fields", "field")
+        .filter(|x| x.scenario() == BASE_SCENARIO)
+        .map(|x| {
+            repository
+                .field_def(x)
+                .with_context(|| format!("invalid field {:?}", x.attr("name")))
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let components = root
+        .items("components", "component")
+        .filter(|x| {
+            let name = x.attr("name");
+            name != Some(HEADER_NAME) && name != Some(TRAILER_NAME)
+        })
+        .map(|x| {
+            let name = x.required_attr("name")?;
+            Ok(Component {
+                name: scenario_name(name, x.scenario()),
+                overlay: OverlayMode::default(),
+                doc: x.documentation(),
+                refs: repository
+                    .refs(x)
+                    .with_context(|| format!("invalid component {}", name))?,
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let messages = root
+        .items("messages", "message")
+        .map(|x| {
+            let name = x.required_attr("name")?;
+            let scenario = x.scenario();
+            let refs = match x.child("structure") {
+                Some(structure) => repository
+                    .refs(structure)
+                    .with_context(|| format!("invalid message {}", name))?,
+                None => vec![],
+            };
+
+            Ok(Message {
+                msgcat: match x.attr("category") {
+                    Some("Session") => MessageCategory::Admin,
+                    _ => MessageCategory::App,
+                },
+                msgtype: x.required_attr("msgType")?.to_string(),
+                name: scenario_name(name, scenario),
+                overlay: OverlayMode::default(),
+                scenario: Some(scenario.to_string()).filter(|x| x != BASE_SCENARIO),
+                doc: x.documentation(),
+                refs,
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(FixSpec {
+        major,
+        minor,
+        servicepack,
+        header: repository.component(HEADER_NAME)?.map(|refs| HeaderSpec {
+            overlay: OverlayMode::default(),
+            refs,
+        }),
+        trailer: repository.component(TRAILER_NAME)?.map(|refs| TrailerSpec {
+            overlay: OverlayMode::default(),
+            refs,
+        }),
+        component: Some(ComponentSpec { items: components }),
+        message: MessagesSpec { items: messages },
+        field: FieldSpec { items: fields },
+    })
+}
+
+/// Parse Orchestra repository file
+pub fn parse<P: AsRef<Path>>(path: P) -> anyhow::Result<FixSpec> {
+    parse_str(&fs::read_to_string(path)?)
+}
+
+/// Parse Orchestra repository XML
+pub fn parse_str(xml: &str) -> anyhow::Result<FixSpec> {
+    let root = read_document(xml)?;
+    spec_from_repository(&root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GeneratorOptions;
+
+    const REPOSITORY: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<fixr:repository xmlns:fixr="http://fixprotocol.io/2020/orchestra/repository" name="FIX.4.4" version="FIX.4.4">
+  <fixr:datatypes>
+    <fixr:datatype name="String" />
+    <fixr:datatype name="SeqNum" />
+    <fixr:datatype name="UTCTimestamp" />
+    <fixr:datatype name="CustomQty" baseType="Qty" />
+    <fixr:datatype name="MultipleValueString" />
+    <fixr:datatype name="MultipleCharValue" />
+    <fixr:datatype name="UTCDate" />
+    <fixr:datatype name="date" />
+    <fixr:datatype name="time" />
+  </fixr:datatypes>
+  <fixr:codeSets>
+    <fixr:codeSet name="MsgTypeCodeSet" id="35" type="String">
+      <fixr:code name="Heartbeat" id="35001" value="0" />
+      <fixr:code name="NewOrderSingle" id="35014" value="D" />
+    </fixr:codeSet>
+    <fixr:codeSet name="SideCodeSet" id="54" type="char">
+      <fixr:code name="Buy" id="54001" value="1">
+        <fixr:annotation>
+          <fixr:documentation purpose="SYNOPSIS">Buy side</fixr:documentation>
+        </fixr:annotation>
+      </fixr:code>
+      <fixr:code name="Sell" id="54002" value="2" />
+    </fixr:codeSet>
+    <fixr:codeSet name="ExecInstCodeSet" id="18" type="MultipleValueString">
+      <fixr:code name="NotHeld" id="18001" value="1" />
+      <fixr:code name="Work" id="18002" value="2" />
+    </fixr:codeSet>
+  </fixr:codeSets>
+  <fixr:fields>
+    <fixr:field id="8" name="BeginString" type="String" />
+    <fixr:field id="9" name="BodyLength" type="Length" />
+    <fixr:field id="10" name="CheckSum" type="String" />
+    <fixr:field id="34" name="MsgSeqNum" type="SeqNum" />
+    <fixr:field id="35" name="MsgType" type="MsgTypeCodeSet" />
+    <fixr:field id="11" name="ClOrdID" type="String" added="FIX.2.7">
+      <fixr:annotation>
+        <fixr:documentation purpose="SYNOPSIS">
+          Unique identifier for Order
+          as assigned by the buy-side
+        </fixr:documentation>
+        <fixr:documentation purpose="ELABORATION">Not kept</fixr:documentation>
+      </fixr:annotation>
+    </fixr:field>
+    <fixr:field id="38" name="OrderQty" type="CustomQty" />
+    <fixr:field id="54" name="Side" type="SideCodeSet" />
+    <fixr:field id="78" name="NoAllocs" type="NumInGroup" />
+    <fixr:field id="79" name="AllocAccount" type="String" />
+    <fixr:field id="1" name="Account" type="String" />
+    <fixr:field id="18" name="ExecInst" type="ExecInstCodeSet" />
+    <fixr:field id="276" name="QuoteCondition" type="MultipleCharValue" />
+    <fixr:field id="272" name="MDEntryDate" type="UTCDate" />
+    <fixr:field id="916" name="StartDate" type="date" />
+    <fixr:field id="273" name="MDEntryTime" type="time" />
+  </fixr:fields>
+  <fixr:components>
+    <fixr:component name="StandardHeader" id="1024">
+      <fixr:fieldRef id="8" presence="required" />
+      <fixr:fieldRef id="9" presence="required" />
+      <fixr:fieldRef id="35" presence="required" />
+      <fixr:fieldRef id="34" presence="required" />
+    </fixr:component>
+    <fixr:component name="StandardTrailer" id="1025">
+      <fixr:fieldRef id="10" presence="required" />
+    </fixr:component>
+    <fixr:component name="Parties" id="1012">
+      <fixr:fieldRef id="1" />
+    </fixr:component>
+  </fixr:components>
+  <fixr:groups>
+    <fixr:group name="PreAllocGrp" id="2045">
+      <fixr:numInGroup id="78" />
+      <fixr:fieldRef id="79" />
+    </fixr:group>
+  </fixr:groups>
+  <fixr:messages>
+    <fixr:message name="Heartbeat" msgType="0" category="Session">
+      <fixr:structure>
+        <fixr:componentRef id="1024" presence="required" />
+        <fixr:componentRef id="1025" presence="required" />
+      </fixr:structure>
+    </fixr:message>
+    <fixr:message name="NewOrderSingle" msgType="D" category="SingleGeneralOrderHandling">
+      <fixr:structure>
+        <fixr:componentRef id="1024" presence="required" />
+        <fixr:fieldRef id="11" presence="required" />
+        <fixr:componentRef id="1012" />
+        <fixr:groupRef id="2045" />
+        <fixr:fieldRef id="54" presence="required" />
+        <fixr:fieldRef id="38" />
+        <fixr:componentRef id="1025" presence="required" />
+      </fixr:structure>
+      <fixr:annotation>
+        <fixr:documentation>New order</fixr:documentation>
+      </fixr:annotation>
+    </fixr:message>
+    <fixr:message name="NewOrderSingle" msgType="D" scenario="dma">
+      <fixr:structure>
+        <fixr:componentRef id="1024" presence="required" />
+        <fixr:fieldRef id="11" presence="required" />
+        <fixr:fieldRef id="54" presence="required" />
+        <fixr:fieldRef id="1" presence="forbidden" />
+        <fixr:componentRef id="1025" presence="required" />
+      </fixr:structure>
+    </fixr:message>
+  </fixr:messages>
+</fixr:repository>
+"#;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("FIX.4.4").unwrap(), (4, 4, 0));
+        assert_eq!(parse_version("FIX.5.0SP2_EP253").unwrap(), (5, 0, 2));
+        assert_eq!(parse_version("FIX.Latest_EP269").unwrap(), (5, 0, 2));
+        assert!(parse_version("FIX.Next").is_err());
+    }
+
+    #[test]
+    fn test_parse_repository() {
+        assert!(is_orchestra(REPOSITORY));
+        let spec = parse_str(REPOSITORY).unwrap();
+        spec.validate().unwrap();
+
+        assert_eq!((spec.major, spec.minor, spec.servicepack), (4, 4, 0));
+        assert_eq!(spec.header.as_ref().unwrap().refs.len(), 4);
+        assert_eq!(spec.trailer.as_ref().unwrap().refs.len(), 1);
+
+        let field = |name: &str| spec.field.items.iter().find(|x| x.name == name).unwrap();
+        assert_eq!(field("OrderQty").field_type, FieldType::Quantity);
+        assert_eq!(field("Side").field_type, FieldType::Char);
+        assert_eq!(field("Side").values[0].description, "Buy");
+        assert_eq!(field("Side").values[0].doc.as_deref(), Some("Buy side"));
+        assert_eq!(field("ExecInst").field_type, FieldType::MultipleValueString);
+        assert_eq!(field("ExecInst").values.len(), 2);
+        assert_eq!(
+            field("QuoteCondition").field_type,
+            FieldType::MultipleCharValue
+        );
+        assert_eq!(field("MDEntryDate").field_type, FieldType::UtcDate);
+        assert_eq!(field("StartDate").field_type, FieldType::Date);
+        assert_eq!(field("MDEntryTime").field_type, FieldType::Time);
+        assert_eq!(
+            field("ClOrdID").doc.as_deref(),
+            Some("Unique identifier for Order as assigned by the buy-side\n\nAdded in FIX.2.7.")
+        );
+
+        let message = &spec.message.items[1];
+        assert_eq!(message.name, "NewOrderSingle");
+        assert_eq!(message.msgcat, MessageCategory::App);
+        assert_eq!(message.doc.as_deref(), Some("New order"));
+        assert_eq!(
+            message.refs,
+            vec![
+                Reference::FieldRef(FieldRef {
+                    name: "ClOrdID".into(),
+                    required: Required::Y,
+                }),
+                Reference::ComponentRef(ComponentRef {
+                    name: "Parties".into(),
+                    required: Required::N,
+                }),
+                Reference::GroupRef(GroupRef {
+                    name: "NoAllocs".into(),
+                    required: Required::N,
+                    refs: vec![Reference::FieldRef(FieldRef {
+                        name: "AllocAccount".into(),
+                        required: Required::N,
+                    })],
+                }),
+                Reference::FieldRef(FieldRef {
+                    name: "Side".into(),
+                    required: Required::Y,
+                }),
+                Reference::FieldRef(FieldRef {
+                    name: "OrderQty".into(),
+                    required: Required::N,
+                }),
+            ]
+        );
+
+        let scenario = &spec.message.items[2];
+        assert_eq!(scenario.name, "NewOrderSingleDma");
+        assert_eq!(scenario.scenario.as_deref(), Some("dma"));
+        assert_eq!(scenario.refs.len(), 2);
+
+        spec.generate_code(&GeneratorOptions::default()).unwrap();
+    }
+
+    #[test]
+    fn test_parse_unknown_reference() {
+        let xml = REPOSITORY.replace(
+            r#"<fixr:fieldRef id="79" />"#,
+            r#"<fixr:fieldRef id="80" />"#,
+        );
+        let err = parse_str(&xml).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "invalid message NewOrderSingle: unknown field ID 80"
+        );
+    }
+}